@@ -6,8 +6,7 @@ use defmt_rtt as _;
 use embassy_executor::Spawner;
 use embassy_rp::peripherals::I2C0;
 use embassy_rp::{bind_interrupts, i2c};
-use embassy_scd41_sensor::{SCD41Error, SCD41Sensor};
-use embassy_time::{Duration, Timer};
+use embassy_scd41_sensor::{DATA_READY_TIMEOUT_MS, SCD41Sensor};
 use panic_probe as _;
 
 bind_interrupts!(struct Irqs {
@@ -25,24 +24,26 @@ async fn main(_spawner: Spawner) -> ! {
     let mut i2c = i2c::I2c::new_async(p.I2C0, scl, sda, Irqs, Default::default());
 
     // Create sensor instance
-    let mut sensor = SCD41Sensor::new(0x62);
+    let mut sensor = SCD41Sensor::new_default(&mut i2c);
+
+    // Initialize sensor and start periodic measurement
+    if sensor.setup().await.is_err() {
+        error!("Failed to set up sensor");
+    }
+    if sensor.start_periodic_measurement().await.is_err() {
+        error!("Failed to start periodic measurement");
+    }
 
     // Read sensor data
     loop {
-        match sensor.read(&mut i2c).await {
+        match sensor.read_when_ready(DATA_READY_TIMEOUT_MS).await {
             Ok(data) => {
                 info!(
                     "Temperature: {}°C, Humidity: {}%, CO2: {}",
                     data.temperature, data.humidity, data.co2
                 );
             }
-            Err(e) => match e {
-                SCD41Error::NoData => error!("No data"),
-                SCD41Error::I2CError => error!("I2C communication error"),
-                SCD41Error::Timeout => error!("Operation timed out"),
-            },
+            Err(e) => error!("Failed to read sensor: {}", e),
         }
-
-        Timer::after(Duration::from_secs(1)).await;
     }
 }
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_embedded_hal::shared_bus::asynch::i2c::I2cDevice;
+use embassy_executor::Spawner;
+use embassy_rp::peripherals::I2C0;
+use embassy_rp::{bind_interrupts, i2c};
+use embassy_scd41_sensor::{DATA_READY_TIMEOUT_MS, SCD41Sensor};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    I2C0_IRQ => i2c::InterruptHandler<I2C0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) -> ! {
+    let p = embassy_rp::init(Default::default());
+
+    let sda = p.PIN_0;
+    let scl = p.PIN_1;
+
+    // Configure I2C, shared by all devices on the same pins
+    let i2c = i2c::I2c::new_async(p.I2C0, scl, sda, Irqs, Default::default());
+    let i2c_bus: Mutex<NoopRawMutex, _> = Mutex::new(i2c);
+
+    // Create sensor instance on its own device handle, other devices on the
+    // bus get their own `I2cDevice::new(&i2c_bus)`
+    let mut sensor = SCD41Sensor::new_default(I2cDevice::new(&i2c_bus));
+
+    // Initialize sensor and start periodic measurement
+    if sensor.setup().await.is_err() {
+        error!("Failed to set up sensor");
+    }
+    if sensor.start_periodic_measurement().await.is_err() {
+        error!("Failed to start periodic measurement");
+    }
+
+    // Read sensor data
+    loop {
+        match sensor.read_when_ready(DATA_READY_TIMEOUT_MS).await {
+            Ok(data) => {
+                info!(
+                    "Temperature: {}°C, Humidity: {}%, CO2: {}",
+                    data.temperature, data.humidity, data.co2
+                );
+            }
+            Err(e) => error!("Failed to read sensor: {}", e),
+        }
+    }
+}
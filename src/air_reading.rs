@@ -0,0 +1,41 @@
+use crate::SCD41Response;
+
+/// Sensor-independent aggregate of air readings, e.g. for telemetry combining several
+/// sensors.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AirReading {
+    /// CO2 concentration in ppm, if measured.
+    pub co2: Option<u16>,
+    /// Temperature in °C.
+    pub temperature: f32,
+    /// Relative humidity in %.
+    pub humidity: f32,
+    /// Pressure in hPa, if measured.
+    pub pressure: Option<f32>,
+}
+
+impl AirReading {
+    /// Combines two readings, taking CO2 and pressure from `self` where present and from
+    /// `other` otherwise. Temperature and humidity are taken from `self`.
+    pub fn merge(self, other: AirReading) -> AirReading {
+        AirReading {
+            co2: self.co2.or(other.co2),
+            temperature: self.temperature,
+            humidity: self.humidity,
+            pressure: self.pressure.or(other.pressure),
+        }
+    }
+}
+
+impl From<SCD41Response> for AirReading {
+    fn from(response: SCD41Response) -> Self {
+        Self {
+            co2: Some(response.co2 as u16),
+            temperature: response.temperature,
+            humidity: response.humidity,
+            pressure: None,
+        }
+    }
+}
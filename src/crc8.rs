@@ -0,0 +1,84 @@
+//! CRC-8 checksum of the Sensirion I2C protocol (polynomial 0x31, initialization 0xff).
+//!
+//! With the `crc-table` feature the checksum is computed from a lookup table generated
+//! at compile time, trading 256 bytes of flash for speed.
+//!
+//! The `skip-crc` feature compiles out the verification of response checksums, saving
+//! cycles per read at the cost of passing corrupted frames on as valid readings. Only
+//! enable it on short, trusted buses. Checksums of written arguments are still sent,
+//! as the sensor requires them.
+
+const POLYNOMIAL: u8 = 0x31;
+const INITIALIZATION: u8 = 0xff;
+
+/// Computes the checksum of `data`.
+#[cfg(not(feature = "crc-table"))]
+pub fn crc8(data: &[u8]) -> u8 {
+    crc8_bitwise(data)
+}
+
+/// Computes the checksum of `data`.
+#[cfg(feature = "crc-table")]
+pub fn crc8(data: &[u8]) -> u8 {
+    crc8_table(data)
+}
+
+#[cfg(any(test, not(feature = "crc-table")))]
+fn crc8_bitwise(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(INITIALIZATION, |crc, &byte| update(crc ^ byte))
+}
+
+#[cfg(any(test, feature = "crc-table"))]
+fn crc8_table(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(INITIALIZATION, |crc, &byte| TABLE[(crc ^ byte) as usize])
+}
+
+/// Shifts one byte worth of bits through the polynomial division.
+const fn update(mut crc: u8) -> u8 {
+    let mut bit = 0;
+    while bit < 8 {
+        crc = if crc & 0x80 != 0 {
+            (crc << 1) ^ POLYNOMIAL
+        } else {
+            crc << 1
+        };
+        bit += 1;
+    }
+    crc
+}
+
+#[cfg(any(test, feature = "crc-table"))]
+static TABLE: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut index = 0;
+    while index < 256 {
+        table[index] = update(index as u8);
+        index += 1;
+    }
+    table
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_datasheet_example() {
+        assert_eq!(crc8(&[0xbe, 0xef]), 0x92);
+    }
+
+    #[test]
+    fn bitwise_and_table_agree_on_all_words() {
+        for word in 0..=u16::MAX {
+            let bytes = word.to_be_bytes();
+            assert_eq!(
+                crc8_bitwise(&bytes),
+                crc8_table(&bytes),
+                "word {:#06x}",
+                word
+            );
+        }
+    }
+}
@@ -1,21 +1,229 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
 
+mod air_reading;
+pub mod crc8;
+mod measurement;
+mod median;
+#[cfg(test)]
+mod mock;
+mod mux;
+mod outlier;
+pub mod power;
+mod protocol;
+#[cfg(feature = "scan")]
+mod scan;
+#[cfg(feature = "blocking")]
+mod scd41_blocking;
 mod scd41_rp;
+mod self_heating;
+mod sensor;
+mod smoothing;
+mod stats;
+mod threshold;
+#[cfg(feature = "units")]
+pub mod units;
 
+pub use air_reading::AirReading;
+pub use measurement::Measurement;
+pub use median::MedianReader;
+pub use mux::{MuxedI2c, TCA9548A_DEFAULT_ADDRESS};
+pub use outlier::OutlierFilter;
+pub use protocol::{
+    DATA_READY_TIMEOUT_MS, LOW_POWER_DATA_READY_TIMEOUT_MS, SCD41_DEFAULT_ADDRESS,
+};
+#[cfg(feature = "scan")]
+pub use scan::{SCAN_RANGE, scan_addresses};
+#[cfg(feature = "blocking")]
+pub use scd41_blocking::SCD41SensorBlocking;
 pub use scd41_rp::SCD41Sensor;
+pub use self_heating::SelfHeatingEstimator;
+pub use sensor::AsyncSensor;
+pub use smoothing::SmoothedReader;
+pub use stats::Stats;
+pub use threshold::{Crossed, Threshold};
 
 #[derive(Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SCD41Response {
     pub co2: f32,
     pub humidity: f32,
     pub temperature: f32,
 }
 
-#[derive(Debug, Clone)]
-pub enum SCD41Error {
+impl SCD41Response {
+    /// Converts raw sensor ticks into physical units as specified in the datasheet.
+    pub(crate) fn from_ticks(co2: u16, temperature: u16, humidity: u16) -> Self {
+        Self {
+            co2: co2 as f32,
+            temperature: tick_to_temperature(temperature),
+            humidity: tick_to_humidity(humidity),
+        }
+    }
+
+    /// Absolute humidity in g/m³, derived from temperature and relative humidity using
+    /// the Magnus approximation of the saturation vapor pressure.
+    pub fn absolute_humidity(&self) -> f32 {
+        let saturation_hpa =
+            6.112f32 * libm::expf(17.62f32 * self.temperature / (243.12f32 + self.temperature));
+        let vapor_hpa = saturation_hpa * self.humidity / 100f32;
+        216.7f32 * vapor_hpa / (273.15f32 + self.temperature)
+    }
+
+    /// Wet-bulb temperature in °C, derived from temperature and relative humidity at
+    /// standard sea level pressure using the empirical formula of Stull (2011).
+    ///
+    /// Valid for 5 to 99 %RH and -20 to 50 °C, where the error stays within -1 to
+    /// +0.65 °C with a mean absolute error below 0.3 °C.
+    pub fn wet_bulb(&self) -> f32 {
+        let t = self.temperature;
+        let rh = self.humidity;
+        t * libm::atanf(0.151977f32 * libm::sqrtf(rh + 8.313659f32))
+            + libm::atanf(t + rh)
+            - libm::atanf(rh - 1.676331f32)
+            + 0.00391838f32 * rh * libm::sqrtf(rh) * libm::atanf(0.023101f32 * rh)
+            - 4.686035f32
+    }
+
+    /// Qualitative air quality band of the CO2 concentration, see [`AirQuality`].
+    pub fn air_quality(&self) -> AirQuality {
+        match co2_to_band(self.co2 as u16) {
+            0 => AirQuality::Excellent,
+            1 => AirQuality::Good,
+            2 => AirQuality::Fair,
+            3 => AirQuality::Poor,
+            _ => AirQuality::Bad,
+        }
+    }
+}
+
+/// Settings of the sensor that are persisted in EEPROM, see
+/// [`SCD41Sensor::persist_if_changed`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SCD41Settings {
+    /// Temperature offset in °C, 4 °C by default.
+    pub temperature_offset: f32,
+    /// Sensor altitude in meters above sea level, 0 by default.
+    pub sensor_altitude: u16,
+    /// Whether automatic self-calibration is enabled, by default it is.
+    pub automatic_self_calibration_enabled: bool,
+    /// Fresh air CO2 concentration in ppm assumed by automatic self-calibration, 400 ppm
+    /// by default.
+    pub automatic_self_calibration_target: u16,
+}
+
+impl Default for SCD41Settings {
+    /// Factory defaults of the sensor.
+    fn default() -> Self {
+        Self {
+            temperature_offset: 4f32,
+            sensor_altitude: 0,
+            automatic_self_calibration_enabled: true,
+            automatic_self_calibration_target: 400,
+        }
+    }
+}
+
+/// Converts a raw temperature tick into °C.
+pub fn tick_to_temperature(tick: u16) -> f32 {
+    -45f32 + 175f32 * tick as f32 / 0xffff as f32
+}
+
+/// Converts a raw relative humidity tick into %.
+pub fn tick_to_humidity(tick: u16) -> f32 {
+    100f32 * tick as f32 / 0xffff as f32
+}
+
+/// Classifies a CO2 concentration in ppm into the [`AirQuality`] bands with integer
+/// comparisons only, from 0 for `Excellent` to 4 for `Bad`.
+pub fn co2_to_band(ppm: u16) -> u8 {
+    match ppm {
+        0..600 => 0,
+        600..800 => 1,
+        800..1000 => 2,
+        1000..1500 => 3,
+        _ => 4,
+    }
+}
+
+/// Air quality bands by CO2 concentration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AirQuality {
+    /// Below 600 ppm.
+    Excellent,
+    /// 600 ppm up to below 800 ppm.
+    Good,
+    /// 800 ppm up to below 1000 ppm.
+    Fair,
+    /// 1000 ppm up to below 1500 ppm.
+    Poor,
+    /// 1500 ppm and above.
+    Bad,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SCD41Error<E> {
     NoData,
-    I2CError,
+    I2CError(E),
     Timeout,
+    CrcMismatch,
+    RecalibrationFailed,
+    SelfTestFailed(u16),
+    Unsupported,
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for SCD41Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SCD41Error::NoData => write!(f, "no data available"),
+            SCD41Error::I2CError(e) => write!(f, "I2C communication error: {:?}", e),
+            SCD41Error::Timeout => write!(f, "timed out waiting for the sensor"),
+            SCD41Error::CrcMismatch => write!(f, "response checksum mismatch"),
+            SCD41Error::RecalibrationFailed => write!(f, "forced recalibration failed"),
+            SCD41Error::SelfTestFailed(malfunction) => {
+                write!(f, "self test failed with malfunction 0x{:04x}", malfunction)
+            }
+            SCD41Error::Unsupported => write!(f, "command not supported by the sensor variant"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SensorVariant {
+    Scd40,
+    Scd41,
+    Unknown(u16),
 }
 
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn wet_bulb_matches_stull_example() {
+        // Worked example of Stull (2011): 20 °C at 50 %RH gives 13.7 °C.
+        let response = crate::mock::response(400f32, 20f32, 50f32);
+        assert!((response.wet_bulb() - 13.7f32).abs() < 0.05f32);
+    }
+
+    #[test]
+    fn no_data_displays_as_no_data_available() {
+        let error = super::SCD41Error::<()>::NoData;
+        assert_eq!(std::format!("{}", error), "no data available");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn response_round_trips_through_serde() {
+        let response = crate::mock::response(415f32, 22.5f32, 41.25f32);
+        let json = serde_json::to_string(&response).unwrap();
+        let decoded: super::SCD41Response = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.co2, response.co2);
+        assert_eq!(decoded.temperature, response.temperature);
+        assert_eq!(decoded.humidity, response.humidity);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::SCD41Response;
+
+/// Reading made of a fixed number of numeric fields, so readings can be processed
+/// generically field by field.
+pub trait Measurement: Clone {
+    /// Number of fields of the reading.
+    const FIELDS: usize;
+
+    /// Value of the field at `index`, `0..FIELDS`.
+    fn field(&self, index: usize) -> f32;
+
+    /// Replaces the value of the field at `index`, `0..FIELDS`.
+    fn set_field(&mut self, index: usize, value: f32);
+}
+
+/// Fields are `co2`, `temperature` and `humidity`, in that order.
+impl Measurement for SCD41Response {
+    const FIELDS: usize = 3;
+
+    fn field(&self, index: usize) -> f32 {
+        match index {
+            0 => self.co2,
+            1 => self.temperature,
+            _ => self.humidity,
+        }
+    }
+
+    fn set_field(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.co2 = value,
+            1 => self.temperature = value,
+            _ => self.humidity = value,
+        }
+    }
+}
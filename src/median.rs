@@ -0,0 +1,127 @@
+use crate::{AsyncSensor, Measurement};
+
+/// Wraps a sensor and returns the field-wise median of its last `N` readings, rejecting
+/// single-sample spikes that a mean would smear.
+///
+/// Until `N` readings have been taken the median covers the readings available so far.
+/// For an even number of readings it is the mean of the two middle values. `N` must be
+/// at least 1, which is checked at compile time.
+pub struct MedianReader<S: AsyncSensor, const N: usize> {
+    sensor: S,
+    readings: [Option<S::Reading>; N],
+    next: usize,
+}
+
+impl<S: AsyncSensor, const N: usize> MedianReader<S, N>
+where
+    S::Reading: Measurement,
+{
+    pub fn new(sensor: S) -> Self {
+        const { assert!(N > 0, "N must be at least 1") };
+        Self {
+            sensor,
+            readings: core::array::from_fn(|_| None),
+            next: 0,
+        }
+    }
+
+    /// Gives access to the wrapped sensor, e.g. to start measurement.
+    pub fn sensor(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    /// Reads the sensor and returns the median of the buffered readings.
+    pub async fn read(&mut self) -> Result<S::Reading, S::Error> {
+        let reading = self.sensor.read().await?;
+        self.readings[self.next] = Some(reading.clone());
+        self.next = (self.next + 1) % N;
+
+        let mut median = reading;
+        let mut values = [0f32; N];
+        for index in 0..S::Reading::FIELDS {
+            let mut count = 0;
+            for reading in self.readings.iter().flatten() {
+                insert_sorted(&mut values[..=count], reading.field(index));
+                count += 1;
+            }
+            let middle = count / 2;
+            let value = if count % 2 == 0 {
+                (values[middle - 1] + values[middle]) / 2f32
+            } else {
+                values[middle]
+            };
+            median.set_field(index, value);
+        }
+        Ok(median)
+    }
+}
+
+/// Inserts `value` into `values`, whose elements but the last are sorted.
+fn insert_sorted(values: &mut [f32], value: f32) {
+    let mut position = values.len() - 1;
+    while position > 0 && values[position - 1] > value {
+        values[position] = values[position - 1];
+        position -= 1;
+    }
+    values[position] = value;
+}
+
+impl<S: AsyncSensor, const N: usize> AsyncSensor for MedianReader<S, N>
+where
+    S::Reading: Measurement,
+{
+    type Reading = S::Reading;
+    type Error = S::Error;
+
+    async fn read(&mut self) -> Result<S::Reading, S::Error> {
+        MedianReader::read(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockSensor, response};
+    use embassy_futures::block_on;
+
+    #[test]
+    fn odd_window_returns_middle_value() {
+        let sensor = MockSensor::new([
+            response(400f32, 20f32, 40f32),
+            response(2000f32, 21f32, 90f32),
+            response(420f32, 22f32, 41f32),
+        ]);
+        let mut reader = MedianReader::<_, 3>::new(sensor);
+        block_on(reader.read()).unwrap();
+        block_on(reader.read()).unwrap();
+        let median = block_on(reader.read()).unwrap();
+        assert_eq!(median.co2, 420f32);
+        assert_eq!(median.temperature, 21f32);
+        assert_eq!(median.humidity, 41f32);
+    }
+
+    #[test]
+    fn even_window_returns_mean_of_middle_values() {
+        let sensor = MockSensor::new([
+            response(400f32, 20f32, 40f32),
+            response(2000f32, 23f32, 90f32),
+            response(420f32, 21f32, 44f32),
+            response(410f32, 22f32, 42f32),
+            response(430f32, 24f32, 46f32),
+        ]);
+        let mut reader = MedianReader::<_, 4>::new(sensor);
+        for _ in 0..3 {
+            block_on(reader.read()).unwrap();
+        }
+        let median = block_on(reader.read()).unwrap();
+        assert_eq!(median.co2, 415f32);
+        assert_eq!(median.temperature, 21.5f32);
+        assert_eq!(median.humidity, 43f32);
+
+        // The oldest reading drops out of the full window.
+        let median = block_on(reader.read()).unwrap();
+        assert_eq!(median.co2, 425f32);
+        assert_eq!(median.temperature, 22.5f32);
+        assert_eq!(median.humidity, 45f32);
+    }
+}
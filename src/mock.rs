@@ -0,0 +1,228 @@
+//! Mock bus and delay for host-side tests of the drivers and wrappers.
+
+use crate::crc8::crc8;
+use crate::{AsyncSensor, SCD41Response};
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+/// Expected bus operation and its outcome.
+#[derive(Debug)]
+pub(crate) enum Transaction {
+    /// A write of exactly these bytes, acknowledged.
+    Write(Vec<u8>),
+    /// A write of exactly these bytes, not acknowledged.
+    WriteNack(Vec<u8>),
+    /// A write of exactly these bytes, failing with arbitration loss.
+    WriteArbitrationLoss(Vec<u8>),
+    /// A read returning these bytes.
+    Read(Vec<u8>),
+    /// A read, not acknowledged.
+    ReadNack,
+}
+
+impl Transaction {
+    /// Write of a bare command.
+    pub(crate) fn command(command: u16) -> Self {
+        Transaction::Write(command.to_be_bytes().to_vec())
+    }
+
+    /// Read of response words, each followed by its checksum.
+    pub(crate) fn words(words: &[u16]) -> Self {
+        Transaction::Read(frame(words))
+    }
+}
+
+/// Encodes words the way the sensor sends them, each followed by its checksum.
+pub(crate) fn frame(words: &[u16]) -> Vec<u8> {
+    words
+        .iter()
+        .flat_map(|word| {
+            let [w0, w1] = word.to_be_bytes();
+            [w0, w1, crc8(&[w0, w1])]
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MockError(embedded_hal::i2c::ErrorKind);
+
+impl embedded_hal::i2c::Error for MockError {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        self.0
+    }
+}
+
+/// Bus asserting that the driver performs exactly the expected transactions on the
+/// expected address.
+pub(crate) struct MockI2c {
+    address: u8,
+    expected: VecDeque<Transaction>,
+}
+
+impl MockI2c {
+    pub(crate) fn new(address: u8, expected: impl IntoIterator<Item = Transaction>) -> Self {
+        Self {
+            address,
+            expected: expected.into_iter().collect(),
+        }
+    }
+
+    /// Asserts that all expected transactions were performed.
+    pub(crate) fn done(&self) {
+        assert!(
+            self.expected.is_empty(),
+            "transactions not performed: {:?}",
+            self.expected
+        );
+    }
+
+    fn execute(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), MockError> {
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource, Operation};
+
+        assert_eq!(address, self.address);
+        for operation in operations {
+            let expected = self.expected.pop_front();
+            match (operation, expected) {
+                (Operation::Write(bytes), Some(Transaction::Write(expected))) => {
+                    assert_eq!(*bytes, &expected[..]);
+                }
+                (Operation::Write(bytes), Some(Transaction::WriteNack(expected))) => {
+                    assert_eq!(*bytes, &expected[..]);
+                    return Err(MockError(ErrorKind::NoAcknowledge(
+                        NoAcknowledgeSource::Address,
+                    )));
+                }
+                (Operation::Write(bytes), Some(Transaction::WriteArbitrationLoss(expected))) => {
+                    assert_eq!(*bytes, &expected[..]);
+                    return Err(MockError(ErrorKind::ArbitrationLoss));
+                }
+                (Operation::Read(buffer), Some(Transaction::Read(data))) => {
+                    assert_eq!(buffer.len(), data.len());
+                    buffer.copy_from_slice(&data);
+                }
+                (Operation::Read(_), Some(Transaction::ReadNack)) => {
+                    return Err(MockError(ErrorKind::NoAcknowledge(
+                        NoAcknowledgeSource::Address,
+                    )));
+                }
+                (operation, expected) => {
+                    panic!("unexpected {:?}, expected {:?}", operation, expected)
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl embedded_hal::i2c::ErrorType for MockI2c {
+    type Error = MockError;
+}
+
+impl embedded_hal_async::i2c::I2c for MockI2c {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), MockError> {
+        self.execute(address, operations)
+    }
+}
+
+impl embedded_hal::i2c::I2c for MockI2c {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), MockError> {
+        self.execute(address, operations)
+    }
+}
+
+/// Bus whose transfers never complete, like a bus held low.
+pub(crate) struct StallingI2c;
+
+impl embedded_hal::i2c::ErrorType for StallingI2c {
+    type Error = MockError;
+}
+
+impl embedded_hal_async::i2c::I2c for StallingI2c {
+    async fn transaction(
+        &mut self,
+        _address: u8,
+        _operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), MockError> {
+        core::future::pending().await
+    }
+}
+
+/// Delay completing immediately.
+#[derive(Default)]
+pub(crate) struct MockDelay;
+
+impl embedded_hal_async::delay::DelayNs for MockDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+impl embedded_hal::delay::DelayNs for MockDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// Delay completing immediately, except for the call at index `stall_call`, which never
+/// completes so the awaiting future can be dropped mid-delay.
+pub(crate) struct StallingDelay {
+    calls: usize,
+    stall_call: usize,
+}
+
+impl StallingDelay {
+    pub(crate) fn new(stall_call: usize) -> Self {
+        Self {
+            calls: 0,
+            stall_call,
+        }
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for StallingDelay {
+    async fn delay_ns(&mut self, _ns: u32) {
+        self.calls += 1;
+        if self.calls - 1 == self.stall_call {
+            core::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Sensor returning the given readings in order.
+pub(crate) struct MockSensor {
+    readings: VecDeque<SCD41Response>,
+}
+
+impl MockSensor {
+    pub(crate) fn new(readings: impl IntoIterator<Item = SCD41Response>) -> Self {
+        Self {
+            readings: readings.into_iter().collect(),
+        }
+    }
+}
+
+impl AsyncSensor for MockSensor {
+    type Reading = SCD41Response;
+    type Error = ();
+
+    async fn read(&mut self) -> Result<SCD41Response, ()> {
+        self.readings.pop_front().ok_or(())
+    }
+}
+
+/// Reading with the given fields.
+pub(crate) fn response(co2: f32, temperature: f32, humidity: f32) -> SCD41Response {
+    SCD41Response {
+        co2,
+        humidity,
+        temperature,
+    }
+}
@@ -0,0 +1,64 @@
+use embedded_hal_async::i2c::{ErrorType, I2c, Operation};
+
+/// Default address of a TCA9548A I2C multiplexer, with all address pins low.
+pub const TCA9548A_DEFAULT_ADDRESS: u8 = 0x70;
+
+/// Bus behind one channel of a TCA9548A I2C multiplexer, for several sensors sharing
+/// the same address.
+///
+/// Selects its channel by writing the channel mask to the multiplexer before every
+/// transaction. Wrap a device handle of a shared bus per channel, e.g.
+/// `MuxedI2c::new(I2cDevice::new(&bus), TCA9548A_DEFAULT_ADDRESS, 3).unwrap()`.
+/// Selection and transaction are not atomic, so sensors behind the same multiplexer
+/// must not be accessed from concurrent tasks.
+pub struct MuxedI2c<I: I2c> {
+    i2c: I,
+    mux_address: u8,
+    channel: u8,
+}
+
+impl<I: I2c> MuxedI2c<I> {
+    /// Creates the bus behind `channel`, 0 to 7, of the multiplexer at `mux_address`.
+    ///
+    /// Returns `None` if the multiplexer has no such channel.
+    pub fn new(i2c: I, mux_address: u8, channel: u8) -> Option<Self> {
+        if channel > 7 {
+            return None;
+        }
+        Some(Self {
+            i2c,
+            mux_address,
+            channel,
+        })
+    }
+}
+
+impl<I: I2c> ErrorType for MuxedI2c<I> {
+    type Error = I::Error;
+}
+
+impl<I: I2c> I2c for MuxedI2c<I> {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.i2c
+            .write(self.mux_address, &[1 << self.channel])
+            .await?;
+        self.i2c.transaction(address, operations).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockI2c;
+
+    #[test]
+    fn rejects_channels_above_seven() {
+        let i2c = || MockI2c::new(TCA9548A_DEFAULT_ADDRESS, []);
+        assert!(MuxedI2c::new(i2c(), TCA9548A_DEFAULT_ADDRESS, 7).is_some());
+        assert!(MuxedI2c::new(i2c(), TCA9548A_DEFAULT_ADDRESS, 8).is_none());
+    }
+}
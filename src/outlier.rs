@@ -0,0 +1,120 @@
+use crate::{AsyncSensor, Measurement};
+
+/// Wraps a sensor and rejects readings jumping by more than a per-field maximum change
+/// from the previously accepted reading, catching CRC-valid but implausible frames.
+///
+/// A rejected reading is replaced by the previously accepted one. A reading confirming
+/// the last rejected one, within the maximum change from it, is accepted so a genuine
+/// step is followed after one sample. The first reading is always accepted.
+pub struct OutlierFilter<S: AsyncSensor> {
+    sensor: S,
+    max_delta: S::Reading,
+    accepted: Option<S::Reading>,
+    rejected: Option<S::Reading>,
+}
+
+impl<S: AsyncSensor> OutlierFilter<S>
+where
+    S::Reading: Measurement,
+{
+    /// Creates a filter accepting a change of up to the respective field of `max_delta`
+    /// between consecutive readings, e.g. `SCD41Response { co2: 500f32, temperature:
+    /// 5f32, humidity: 20f32 }`.
+    pub fn new(sensor: S, max_delta: S::Reading) -> Self {
+        Self {
+            sensor,
+            max_delta,
+            accepted: None,
+            rejected: None,
+        }
+    }
+
+    /// Gives access to the wrapped sensor, e.g. to start measurement.
+    pub fn sensor(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    /// Reads the sensor and returns the reading, or the previously accepted one if the
+    /// reading is rejected.
+    pub async fn read(&mut self) -> Result<S::Reading, S::Error> {
+        let reading = self.sensor.read().await?;
+        let plausible = |reference: &Option<S::Reading>| {
+            reference.as_ref().is_some_and(|reference| {
+                (0..S::Reading::FIELDS).all(|index| {
+                    let delta = reading.field(index) - reference.field(index);
+                    delta.abs() <= self.max_delta.field(index)
+                })
+            })
+        };
+        match &self.accepted {
+            Some(accepted) if !plausible(&self.accepted) && !plausible(&self.rejected) => {
+                let accepted = accepted.clone();
+                self.rejected = Some(reading);
+                Ok(accepted)
+            }
+            _ => {
+                self.accepted = Some(reading.clone());
+                self.rejected = None;
+                Ok(reading)
+            }
+        }
+    }
+}
+
+impl<S: AsyncSensor> AsyncSensor for OutlierFilter<S>
+where
+    S::Reading: Measurement,
+{
+    type Reading = S::Reading;
+    type Error = S::Error;
+
+    async fn read(&mut self) -> Result<S::Reading, S::Error> {
+        OutlierFilter::read(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockSensor, response};
+    use embassy_futures::block_on;
+
+    fn filter(readings: impl IntoIterator<Item = f32>) -> OutlierFilter<MockSensor> {
+        let sensor = MockSensor::new(readings.into_iter().map(|co2| response(co2, 20f32, 40f32)));
+        OutlierFilter::new(sensor, response(500f32, 5f32, 20f32))
+    }
+
+    #[test]
+    fn accepts_first_reading() {
+        let mut filter = filter([3000f32]);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 3000f32);
+    }
+
+    #[test]
+    fn replaces_spike_by_previous_reading() {
+        let mut filter = filter([400f32, 2000f32]);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 400f32);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 400f32);
+        assert!(filter.rejected.is_some());
+    }
+
+    #[test]
+    fn accepts_step_confirmed_by_next_reading() {
+        let mut filter = filter([400f32, 2000f32, 2100f32, 2050f32]);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 400f32);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 400f32);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 2100f32);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 2050f32);
+    }
+
+    #[test]
+    fn return_to_baseline_clears_rejected_reading() {
+        let mut filter = filter([400f32, 2000f32, 420f32, 2000f32]);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 400f32);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 400f32);
+        assert_eq!(block_on(filter.read()).unwrap().co2, 420f32);
+        assert!(filter.rejected.is_none());
+        // The earlier spike no longer confirms a repeated one.
+        assert_eq!(block_on(filter.read()).unwrap().co2, 420f32);
+    }
+}
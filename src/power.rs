@@ -0,0 +1,20 @@
+//! Average supply current of the SCD41 measurement modes at 3.3 V, as typical values from
+//! the Sensirion SCD4x datasheet, for battery sizing.
+
+/// Average current in periodic measurement, one sample every 5 seconds.
+pub const PERIODIC_CURRENT_MA: f32 = 15f32;
+
+/// Average current in low power periodic measurement, one sample every 30 seconds.
+pub const LOW_POWER_PERIODIC_CURRENT_MA: f32 = 3.2f32;
+
+/// Average current with one single shot measurement every 5 minutes and idle mode in
+/// between.
+pub const SINGLE_SHOT_5_MIN_CURRENT_MA: f32 = 0.45f32;
+
+/// Estimates the charge in mAh drawn over `hours` in a mode averaging `current_ma`
+/// while the sensor is operated for the fraction `duty_cycle` (0 to 1) of the time.
+///
+/// Current drawn outside the duty cycle, e.g. in power-down mode, is neglected.
+pub fn estimated_charge_mah(current_ma: f32, duty_cycle: f32, hours: f32) -> f32 {
+    current_ma * duty_cycle.clamp(0f32, 1f32) * hours
+}
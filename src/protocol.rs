@@ -0,0 +1,216 @@
+//! Command set, timing and data encoding of the SCD4x I2C interface shared by the
+//! async and blocking drivers.
+
+use crate::crc8::crc8;
+use crate::{SCD41Error, SensorVariant};
+use embedded_hal_async::i2c::{Error, ErrorKind};
+
+pub(crate) const CMD_START_PERIODIC_MEASUREMENT: u16 = 0x21b1;
+pub(crate) const CMD_START_LOW_POWER_PERIODIC_MEASUREMENT: u16 = 0x21ac;
+pub(crate) const CMD_READ_MEASUREMENT: u16 = 0xec05;
+pub(crate) const CMD_GET_DATA_READY_STATUS: u16 = 0xe4b8;
+pub(crate) const CMD_STOP_PERIODIC_MEASUREMENT: u16 = 0x3f86;
+pub(crate) const CMD_SET_TEMPERATURE_OFFSET: u16 = 0x241d;
+pub(crate) const CMD_GET_TEMPERATURE_OFFSET: u16 = 0x2318;
+pub(crate) const CMD_SET_SENSOR_ALTITUDE: u16 = 0x2427;
+pub(crate) const CMD_GET_SENSOR_ALTITUDE: u16 = 0x2322;
+pub(crate) const CMD_SET_AMBIENT_PRESSURE: u16 = 0xe000;
+pub(crate) const CMD_PERFORM_FORCED_RECALIBRATION: u16 = 0x362f;
+pub(crate) const CMD_SET_AUTOMATIC_SELF_CALIBRATION_ENABLED: u16 = 0x2416;
+pub(crate) const CMD_GET_AUTOMATIC_SELF_CALIBRATION_ENABLED: u16 = 0x2313;
+pub(crate) const CMD_SET_AUTOMATIC_SELF_CALIBRATION_TARGET: u16 = 0x243a;
+pub(crate) const CMD_GET_AUTOMATIC_SELF_CALIBRATION_TARGET: u16 = 0x233f;
+pub(crate) const CMD_PERSIST_SETTINGS: u16 = 0x3615;
+pub(crate) const CMD_GET_SERIAL_NUMBER: u16 = 0x3682;
+pub(crate) const CMD_PERFORM_SELF_TEST: u16 = 0x3639;
+pub(crate) const CMD_PERFORM_FACTORY_RESET: u16 = 0x3632;
+pub(crate) const CMD_REINIT: u16 = 0x3646;
+pub(crate) const CMD_MEASURE_SINGLE_SHOT: u16 = 0x219d;
+pub(crate) const CMD_MEASURE_SINGLE_SHOT_RHT_ONLY: u16 = 0x2196;
+pub(crate) const CMD_POWER_DOWN: u16 = 0x36e0;
+pub(crate) const CMD_WAKE_UP: u16 = 0x36f6;
+pub(crate) const CMD_GET_SENSOR_VARIANT: u16 = 0x202f;
+
+/// Time the sensor needs after a start command before the first sample is available.
+pub(crate) const FIRST_SAMPLE_DELAY_MS: u32 = 5000;
+/// Time the sensor needs after a low power start command before the first sample is available.
+pub(crate) const LOW_POWER_FIRST_SAMPLE_DELAY_MS: u32 = 30000;
+/// Command execution time before the response of a read command can be fetched.
+pub(crate) const READ_DELAY_MS: u32 = 1;
+/// Time the sensor ignores commands after stopping periodic measurement.
+pub(crate) const STOP_DELAY_MS: u32 = 500;
+/// Execution time of commands that write a configuration value.
+pub(crate) const WRITE_DELAY_MS: u32 = 1;
+/// Execution time of the forced recalibration.
+pub(crate) const FORCED_RECALIBRATION_DELAY_MS: u32 = 400;
+/// Time needed to write the settings to EEPROM.
+pub(crate) const PERSIST_SETTINGS_DELAY_MS: u32 = 800;
+/// Execution time of the self test.
+pub(crate) const SELF_TEST_DELAY_MS: u32 = 10000;
+/// Execution time of the factory reset.
+pub(crate) const FACTORY_RESET_DELAY_MS: u32 = 1200;
+/// Time needed to reload the settings from EEPROM.
+pub(crate) const REINIT_DELAY_MS: u32 = 30;
+/// Execution time of a single shot measurement.
+pub(crate) const SINGLE_SHOT_DELAY_MS: u32 = 5000;
+/// Execution time of a temperature and humidity only single shot measurement.
+pub(crate) const SINGLE_SHOT_RHT_ONLY_DELAY_MS: u32 = 50;
+/// Time the sensor needs to boot after a wake-up.
+pub(crate) const WAKE_UP_DELAY_MS: u32 = 30;
+/// Interval between data-ready checks while waiting for a new sample.
+pub(crate) const DATA_READY_POLL_INTERVAL_MS: u32 = 100;
+/// Interval between calls of the wait callback during long commands.
+pub(crate) const WAIT_CALLBACK_INTERVAL_MS: u32 = 100;
+/// Default delay between attempts of a retried read.
+pub(crate) const DEFAULT_RETRY_DELAY_MS: u32 = 10;
+
+/// Fixed I2C address of the SCD4x sensors.
+pub const SCD41_DEFAULT_ADDRESS: u8 = 0x62;
+
+/// Recommended data-ready timeout in milliseconds for periodic measurement, one second
+/// above the 5 second cadence.
+pub const DATA_READY_TIMEOUT_MS: u32 = 6000;
+
+/// Recommended data-ready timeout in milliseconds for low power periodic measurement, one
+/// second above the 30 second cadence.
+pub const LOW_POWER_DATA_READY_TIMEOUT_MS: u32 = 31000;
+
+/// Largest response of any command, three words with their checksums.
+pub(crate) const MAX_RESPONSE_LEN: usize = 9;
+
+/// Encodes a command followed by a CRC-protected argument word.
+pub(crate) fn encode_word(command: u16, word: u16) -> [u8; 5] {
+    let [c0, c1] = command.to_be_bytes();
+    let [w0, w1] = word.to_be_bytes();
+    [c0, c1, w0, w1, crc8(&[w0, w1])]
+}
+
+/// Decodes response words, verifying the checksum following every word unless the
+/// `skip-crc` feature is enabled.
+pub(crate) fn decode_words<E, const N: usize>(buf: &[u8]) -> Result<[u16; N], SCD41Error<E>> {
+    let mut words = [0u16; N];
+    for (word, chunk) in words.iter_mut().zip(buf.chunks_exact(3)) {
+        if cfg!(not(feature = "skip-crc")) && crc8(&chunk[..2]) != chunk[2] {
+            return Err(SCD41Error::CrcMismatch);
+        }
+        *word = u16::from_be_bytes([chunk[0], chunk[1]]);
+    }
+    Ok(words)
+}
+
+pub(crate) fn is_data_ready(status: u16) -> bool {
+    status & 0x07ff != 0
+}
+
+/// Encodes a temperature offset, rounded to the nearest tick and clamped to the
+/// supported range of 0 to 175 °C.
+pub(crate) fn temperature_offset_to_ticks(celsius: f32) -> u16 {
+    libm::roundf(celsius.clamp(0f32, 175f32) * 65535f32 / 175f32) as u16
+}
+
+pub(crate) fn temperature_offset_from_ticks(ticks: u16) -> f32 {
+    175f32 * ticks as f32 / 65535f32
+}
+
+pub(crate) fn pascals_to_hectopascals(pascals: u32) -> u16 {
+    (pascals / 100).min(u16::MAX as u32) as u16
+}
+
+/// Decodes the forced recalibration result into the applied correction in ppm.
+pub(crate) fn decode_recalibration<E>(word: u16) -> Result<i16, SCD41Error<E>> {
+    if word == 0xffff {
+        return Err(SCD41Error::RecalibrationFailed);
+    }
+    Ok((word as i32 - 0x8000) as i16)
+}
+
+pub(crate) fn decode_serial_number(words: [u16; 3]) -> u64 {
+    words
+        .iter()
+        .fold(0u64, |serial, &word| (serial << 16) | word as u64)
+}
+
+pub(crate) fn decode_self_test<E>(word: u16) -> Result<(), SCD41Error<E>> {
+    match word {
+        0 => Ok(()),
+        malfunction => Err(SCD41Error::SelfTestFailed(malfunction)),
+    }
+}
+
+pub(crate) fn decode_variant(word: u16) -> SensorVariant {
+    match word >> 12 {
+        0b0000 => SensorVariant::Scd40,
+        0b0001 => SensorVariant::Scd41,
+        other => SensorVariant::Unknown(other),
+    }
+}
+
+/// Rejects SCD41-only commands if the sensor is known to be an SCD40.
+pub(crate) fn ensure_scd41<E>(variant: &Option<SensorVariant>) -> Result<(), SCD41Error<E>> {
+    match variant {
+        Some(SensorVariant::Scd40) => Err(SCD41Error::Unsupported),
+        _ => Ok(()),
+    }
+}
+
+/// Checks whether `error` is the sensor not acknowledging, as opposed to other bus
+/// errors.
+pub(crate) fn is_nack<E: Error>(error: &SCD41Error<E>) -> bool {
+    matches!(error, SCD41Error::I2CError(e) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)))
+}
+
+/// Maps a bus error to `SCD41Error::NoData` for commands used to detect the sensor.
+pub(crate) fn no_response<E>(error: SCD41Error<E>) -> SCD41Error<E> {
+    match error {
+        SCD41Error::I2CError(_) => SCD41Error::NoData,
+        other => other,
+    }
+}
+
+/// Maps a missing acknowledge to `SCD41Error::NoData` for reads of the measurement,
+/// which the sensor does not acknowledge until a new sample is available.
+pub(crate) fn no_sample<E: Error>(error: SCD41Error<E>) -> SCD41Error<E> {
+    if is_nack(&error) {
+        SCD41Error::NoData
+    } else {
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_words_accepts_valid_frame() {
+        let words = decode_words::<(), 1>(&[0xbe, 0xef, 0x92]).unwrap();
+        assert_eq!(words, [0xbeef]);
+    }
+
+    #[test]
+    fn temperature_offset_rounds_to_nearest_tick() {
+        assert_eq!(temperature_offset_to_ticks(6.2f32), 0x0912);
+        assert_eq!(temperature_offset_to_ticks(5.4f32), 0x07e6);
+    }
+
+    #[test]
+    fn temperature_offset_round_trips() {
+        for ticks in [0u16, 1, 0x0912, 0x7fff, 0xffff] {
+            let celsius = temperature_offset_from_ticks(ticks);
+            assert_eq!(temperature_offset_to_ticks(celsius), ticks);
+        }
+    }
+
+    #[test]
+    fn temperature_offset_is_clamped() {
+        assert_eq!(temperature_offset_to_ticks(-1f32), 0);
+        assert_eq!(temperature_offset_to_ticks(200f32), 0xffff);
+    }
+
+    #[test]
+    #[cfg(not(feature = "skip-crc"))]
+    fn decode_words_rejects_corrupted_frame() {
+        let result = decode_words::<(), 1>(&[0xbe, 0xee, 0x92]);
+        assert_eq!(result, Err(SCD41Error::CrcMismatch));
+    }
+}
@@ -0,0 +1,28 @@
+use core::ops::RangeInclusive;
+use embedded_hal::i2c::I2c;
+
+/// Range of non-reserved 7-bit I2C addresses.
+pub const SCAN_RANGE: RangeInclusive<u8> = 0x08..=0x77;
+
+/// Probes every address in `range` with a one byte read and stores the addresses that
+/// acknowledge in `found`, for bus bring-up.
+///
+/// Returns the filled part of `found`. Scanning stops once `found` is full. A running
+/// SCD41 answers on `0x62`, its response is discarded.
+pub fn scan_addresses<'a, I: I2c>(
+    i2c: &mut I,
+    range: RangeInclusive<u8>,
+    found: &'a mut [u8],
+) -> &'a [u8] {
+    let mut count = 0;
+    for address in range {
+        if count == found.len() {
+            break;
+        }
+        if i2c.read(address, &mut [0u8]).is_ok() {
+            found[count] = address;
+            count += 1;
+        }
+    }
+    &found[..count]
+}
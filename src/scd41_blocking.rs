@@ -0,0 +1,717 @@
+use crate::protocol::*;
+use crate::{SCD41Error, SCD41Response, SCD41Settings, SelfHeatingEstimator, SensorVariant};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// Blocking SCD41 driver on any bus implementing the embedded-hal `I2c` trait, for
+/// projects without an async executor.
+///
+/// Mirrors [`SCD41Sensor`](crate::SCD41Sensor), waiting for command execution through
+/// the blocking `DelayNs`.
+pub struct SCD41SensorBlocking<I: I2c, D: DelayNs> {
+    i2c: I,
+    delay: D,
+    address: u8,
+    variant: Option<SensorVariant>,
+    retry_delay_ms: u32,
+    stale_read_guard: bool,
+    discard_warmup_samples: u8,
+    samples_since_start: u32,
+}
+
+impl<I: I2c, D: DelayNs> SCD41SensorBlocking<I, D> {
+    /// Creates a sensor on the given bus, the SCD41 listens on [`SCD41_DEFAULT_ADDRESS`].
+    pub fn new(i2c: I, delay: D, address: u8) -> Self {
+        Self {
+            i2c,
+            delay,
+            address,
+            variant: None,
+            retry_delay_ms: DEFAULT_RETRY_DELAY_MS,
+            stale_read_guard: false,
+            discard_warmup_samples: 0,
+            samples_since_start: 0,
+        }
+    }
+
+    /// Creates a sensor on the given bus at [`SCD41_DEFAULT_ADDRESS`].
+    pub fn new_default(i2c: I, delay: D) -> Self {
+        Self::new(i2c, delay, SCD41_DEFAULT_ADDRESS)
+    }
+
+    /// Brings the sensor into a known idle state and verifies it is present.
+    ///
+    /// Wakes the sensor in case [`Self::power_down`] left it in sleep mode before a reset
+    /// of the host, ignoring a missing acknowledge like [`Self::wake_up`]. Then stops a
+    /// measurement left running from a previous boot, reloads the settings from EEPROM,
+    /// reads the serial number and detects the sensor variant. Returns
+    /// `SCD41Error::NoData` if the sensor does not respond.
+    ///
+    /// Variant detection is best-effort: firmware that does not acknowledge the variant
+    /// command leaves the variant unknown, and SCD41-only commands are then not refused.
+    pub fn setup(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_wake_up().map_err(no_response)?;
+        self.stop_periodic_measurement().map_err(no_response)?;
+        self.reinit()?;
+        self.get_serial_number().map_err(no_response)?;
+        match self.get_sensor_variant() {
+            Err(error) if !is_nack(&error) => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Starts periodic measurement and waits until the first sample is available.
+    ///
+    /// The sensor updates the measurement every 5 seconds afterwards.
+    pub fn start_periodic_measurement(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_START_PERIODIC_MEASUREMENT)?;
+        self.samples_since_start = 0;
+        self.delay.delay_ms(FIRST_SAMPLE_DELAY_MS);
+        Ok(())
+    }
+
+    /// Starts low power periodic measurement and waits until the first sample is available.
+    ///
+    /// The sensor updates the measurement every 30 seconds instead of 5, drawing about
+    /// 3.2 mA on average instead of 15 mA at 3.3 V. Wait for samples with
+    /// [`LOW_POWER_DATA_READY_TIMEOUT_MS`].
+    pub fn start_low_power_periodic_measurement(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_START_LOW_POWER_PERIODIC_MEASUREMENT)?;
+        self.samples_since_start = 0;
+        self.delay.delay_ms(LOW_POWER_FIRST_SAMPLE_DELAY_MS);
+        Ok(())
+    }
+
+    /// Stops periodic measurement and waits until the sensor accepts new commands.
+    ///
+    /// Safe to call when measurement was never started.
+    pub fn stop_periodic_measurement(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_STOP_PERIODIC_MEASUREMENT)?;
+        self.delay.delay_ms(STOP_DELAY_MS);
+        Ok(())
+    }
+
+    /// Reads the latest measurement taken in periodic mode.
+    ///
+    /// The sensor empties its buffer on read-out and does not acknowledge reads until
+    /// the next sample is taken, reading again before then returns
+    /// `SCD41Error::NoData`.
+    pub fn read(&mut self) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        if self.stale_read_guard && !self.get_data_ready_status()? {
+            return Err(SCD41Error::NoData);
+        }
+        self.read_measurement()
+    }
+
+    /// Enables or disables the check of [`Self::read`] for a new sample, disabled by
+    /// default.
+    ///
+    /// Without the check an empty measurement buffer is detected from the sensor not
+    /// acknowledging the read, which relies on the bus reporting
+    /// `ErrorKind::NoAcknowledge`. With it, [`Self::read`] queries the data-ready status
+    /// first and returns `SCD41Error::NoData` without reading, for buses reporting a
+    /// missing acknowledge as another error kind, at the cost of a query per read.
+    pub fn set_stale_read_guard(&mut self, enabled: bool) {
+        self.stale_read_guard = enabled;
+    }
+
+    /// Reads the latest measurement as CRC-checked raw `(co2, temperature, humidity)`
+    /// ticks without floating point conversion.
+    ///
+    /// CO2 ticks are ppm, convert the others with
+    /// [`tick_to_temperature`](crate::tick_to_temperature) and
+    /// [`tick_to_humidity`](crate::tick_to_humidity).
+    /// Returns `SCD41Error::NoData` if no new sample is available, like [`Self::read`].
+    pub fn read_raw_ticks(&mut self) -> Result<(u16, u16, u16), SCD41Error<I::Error>> {
+        let [co2, temperature, humidity] =
+            self.read_words(CMD_READ_MEASUREMENT).map_err(no_sample)?;
+        Ok((co2, temperature, humidity))
+    }
+
+    /// Reads the latest measurement, making up to `attempts` attempts on bus errors.
+    ///
+    /// Only `SCD41Error::I2CError` is retried, after waiting the retry delay, other errors
+    /// such as `SCD41Error::CrcMismatch` are returned immediately. `SCD41Error::NoData`
+    /// for a read before a new sample is available is not retried either, wait for the
+    /// sample with [`Self::read_when_ready`] instead. Returns the last bus error if all
+    /// attempts fail. The read is attempted at least once.
+    pub fn read_retry(&mut self, attempts: u8) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        let mut attempt = 1;
+        loop {
+            match self.read() {
+                Err(SCD41Error::I2CError(_)) if attempt < attempts => {
+                    attempt += 1;
+                    self.delay.delay_ms(self.retry_delay_ms);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sets the delay between attempts of [`Self::read_retry`], 10 ms by default.
+    pub fn set_retry_delay_ms(&mut self, millis: u32) {
+        self.retry_delay_ms = millis;
+    }
+
+    /// Performs an on-demand measurement from idle mode and reads it.
+    ///
+    /// The measurement takes 5 seconds. Supported by the SCD41 only, see
+    /// [`SCD41SensorBlocking::get_sensor_variant`].
+    pub fn measure_single_shot(&mut self) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_command(CMD_MEASURE_SINGLE_SHOT)?;
+        self.delay.delay_ms(SINGLE_SHOT_DELAY_MS);
+        self.read_measurement()
+    }
+
+    /// Performs an on-demand temperature and humidity measurement from idle mode and
+    /// returns `(temperature, humidity)`, CO2 is not measured.
+    ///
+    /// The measurement takes 50 ms. Supported by the SCD41 only, see
+    /// [`SCD41SensorBlocking::get_sensor_variant`].
+    pub fn measure_single_shot_rht_only(&mut self) -> Result<(f32, f32), SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_command(CMD_MEASURE_SINGLE_SHOT_RHT_ONLY)?;
+        self.delay.delay_ms(SINGLE_SHOT_RHT_ONLY_DELAY_MS);
+        let response = self.read_measurement()?;
+        Ok((response.temperature, response.humidity))
+    }
+
+    /// Waits until a new sample is available and reads it.
+    ///
+    /// Returns `SCD41Error::Timeout` if no sample becomes ready within `timeout_ms`,
+    /// see [`DATA_READY_TIMEOUT_MS`] and [`LOW_POWER_DATA_READY_TIMEOUT_MS`] for the values
+    /// matching periodic measurement. Samples within the warm-up period set with
+    /// [`Self::set_discard_warmup_samples`] are read and discarded, the timeout applies
+    /// to every sample.
+    pub fn read_when_ready(
+        &mut self,
+        timeout_ms: u32,
+    ) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        loop {
+            let mut waited_ms = 0;
+            while !self.get_data_ready_status()? {
+                if waited_ms >= timeout_ms {
+                    return Err(SCD41Error::Timeout);
+                }
+                self.delay.delay_ms(DATA_READY_POLL_INTERVAL_MS);
+                waited_ms += DATA_READY_POLL_INTERVAL_MS;
+            }
+            let response = self.read_measurement()?;
+            self.samples_since_start = self.samples_since_start.saturating_add(1);
+            if self.samples_since_start > self.discard_warmup_samples as u32 {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Sets the number of samples [`Self::read_when_ready`] and [`Self::try_read`]
+    /// discard after a start of periodic measurement, 0 by default.
+    ///
+    /// The first readings after start-up are less accurate while the sensor conditions.
+    pub fn set_discard_warmup_samples(&mut self, samples: u8) {
+        self.discard_warmup_samples = samples;
+    }
+
+    /// Returns the number of samples read by [`Self::read_when_ready`] and
+    /// [`Self::try_read`] since periodic measurement was last started, including
+    /// discarded warm-up samples.
+    pub fn samples_since_start(&self) -> u32 {
+        self.samples_since_start
+    }
+
+    /// Reads a new sample if one is available, without waiting.
+    ///
+    /// Returns `Ok(None)` if no sample became available since the last read, for polling
+    /// loops that must not block on data-ready. Samples within the warm-up period set
+    /// with [`Self::set_discard_warmup_samples`] are read and discarded, returning
+    /// `Ok(None)` as well.
+    pub fn try_read(&mut self) -> Result<Option<SCD41Response>, SCD41Error<I::Error>> {
+        if !self.get_data_ready_status()? {
+            return Ok(None);
+        }
+        let response = self.read_measurement()?;
+        self.samples_since_start = self.samples_since_start.saturating_add(1);
+        if self.samples_since_start > self.discard_warmup_samples as u32 {
+            Ok(Some(response))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Checks whether the sensor acknowledges a data-ready status query, e.g. for a
+    /// health check of a sensor that may have been disconnected.
+    ///
+    /// Returns `Ok(false)` if the sensor does not acknowledge, which it also does not
+    /// while powered down or busy executing a command. Other bus errors are returned as
+    /// `SCD41Error::I2CError`.
+    pub fn is_present(&mut self) -> Result<bool, SCD41Error<I::Error>> {
+        match self.get_data_ready_status() {
+            Ok(_) => Ok(true),
+            Err(error) if is_nack(&error) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks whether a new sample is available since the last read.
+    pub fn get_data_ready_status(&mut self) -> Result<bool, SCD41Error<I::Error>> {
+        let [status] = self.read_words(CMD_GET_DATA_READY_STATUS)?;
+        Ok(is_data_ready(status))
+    }
+
+    /// Sets the temperature offset in °C used to compensate self-heating of the sensor.
+    ///
+    /// The offset is rounded to the sensor resolution of about 0.003 °C and clamped to
+    /// the supported range of 0 to 175 °C. Only valid in idle mode, the sensor does not
+    /// acknowledge the command during periodic measurement and `SCD41Error::I2CError`
+    /// is returned.
+    pub fn set_temperature_offset(&mut self, celsius: f32) -> Result<(), SCD41Error<I::Error>> {
+        let ticks = temperature_offset_to_ticks(celsius);
+        self.write_word(CMD_SET_TEMPERATURE_OFFSET, ticks)
+    }
+
+    /// Gets the temperature offset in °C.
+    ///
+    /// Only valid in idle mode, the sensor does not acknowledge the command during
+    /// periodic measurement and `SCD41Error::I2CError` is returned.
+    pub fn get_temperature_offset(&mut self) -> Result<f32, SCD41Error<I::Error>> {
+        let [ticks] = self.read_words(CMD_GET_TEMPERATURE_OFFSET)?;
+        Ok(temperature_offset_from_ticks(ticks))
+    }
+
+    /// Sets the temperature offset estimated by `estimator` from readings taken with the
+    /// current offset, resets the estimator and returns the offset. Does nothing and
+    /// returns `None` before the first estimator update.
+    ///
+    /// Call it periodically, feeding the estimator between calls, to track the
+    /// self-heating over time. Only valid in idle mode, stop periodic measurement
+    /// beforehand. Persist the offset with [`Self::persist_settings`] to keep it across
+    /// power cycles.
+    pub fn apply_self_heating_model(
+        &mut self,
+        estimator: &mut SelfHeatingEstimator,
+    ) -> Result<Option<f32>, SCD41Error<I::Error>> {
+        if estimator.deviation().is_none() {
+            return Ok(None);
+        }
+        let current_offset = self.get_temperature_offset()?;
+        let offset = estimator.converged_offset(current_offset);
+        if let Some(offset) = offset {
+            self.set_temperature_offset(offset)?;
+            estimator.reset();
+        }
+        Ok(offset)
+    }
+
+    /// Sets the sensor altitude in meters above sea level used for pressure compensation.
+    ///
+    /// Only valid in idle mode, the sensor does not acknowledge the command during
+    /// periodic measurement and `SCD41Error::I2CError` is returned.
+    pub fn set_sensor_altitude(&mut self, meters: u16) -> Result<(), SCD41Error<I::Error>> {
+        self.write_word(CMD_SET_SENSOR_ALTITUDE, meters)
+    }
+
+    /// Gets the sensor altitude in meters above sea level.
+    ///
+    /// Only valid in idle mode, the sensor does not acknowledge the command during
+    /// periodic measurement and `SCD41Error::I2CError` is returned.
+    pub fn get_sensor_altitude(&mut self) -> Result<u16, SCD41Error<I::Error>> {
+        let [meters] = self.read_words(CMD_GET_SENSOR_ALTITUDE)?;
+        Ok(meters)
+    }
+
+    /// Sets the ambient pressure in Pa used for pressure compensation.
+    ///
+    /// Overrides the sensor altitude and, unlike it, may be updated during periodic
+    /// measurement, so readings of an external barometer can be fed in continuously.
+    pub fn set_ambient_pressure(&mut self, pascals: u32) -> Result<(), SCD41Error<I::Error>> {
+        let hectopascals = pascals_to_hectopascals(pascals);
+        self.write_word(CMD_SET_AMBIENT_PRESSURE, hectopascals)
+    }
+
+    /// Performs forced recalibration against a known CO2 concentration and returns the
+    /// applied correction in ppm.
+    ///
+    /// The sensor must have been operated in periodic measurement for at least 3 minutes
+    /// in an environment with constant, known CO2 concentration, and periodic measurement
+    /// must be stopped before calling. Returns `SCD41Error::RecalibrationFailed` if the
+    /// sensor rejects the recalibration.
+    pub fn perform_forced_recalibration(
+        &mut self,
+        target_ppm: u16,
+    ) -> Result<i16, SCD41Error<I::Error>> {
+        self.write_word(CMD_PERFORM_FORCED_RECALIBRATION, target_ppm)?;
+        self.delay.delay_ms(FORCED_RECALIBRATION_DELAY_MS);
+        let [correction] = self.read_response()?;
+        decode_recalibration(correction)
+    }
+
+    /// Enables or disables automatic self-calibration (ASC).
+    ///
+    /// ASC assumes the sensor is regularly exposed to fresh air, disable it in sealed
+    /// environments and use forced recalibration instead. Only valid in idle mode.
+    pub fn set_automatic_self_calibration_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.write_word(CMD_SET_AUTOMATIC_SELF_CALIBRATION_ENABLED, enabled as u16)
+    }
+
+    /// Checks whether automatic self-calibration (ASC) is enabled. Only valid in idle mode.
+    pub fn get_automatic_self_calibration_enabled(&mut self) -> Result<bool, SCD41Error<I::Error>> {
+        let [enabled] = self.read_words(CMD_GET_AUTOMATIC_SELF_CALIBRATION_ENABLED)?;
+        Ok(enabled != 0)
+    }
+
+    /// Sets the CO2 concentration in ppm that automatic self-calibration (ASC) assumes
+    /// for fresh air, 400 ppm by default. Only valid in idle mode.
+    pub fn set_automatic_self_calibration_target(
+        &mut self,
+        ppm: u16,
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.write_word(CMD_SET_AUTOMATIC_SELF_CALIBRATION_TARGET, ppm)
+    }
+
+    /// Gets the CO2 concentration in ppm that automatic self-calibration (ASC) assumes
+    /// for fresh air. Only valid in idle mode.
+    pub fn get_automatic_self_calibration_target(&mut self) -> Result<u16, SCD41Error<I::Error>> {
+        let [ppm] = self.read_words(CMD_GET_AUTOMATIC_SELF_CALIBRATION_TARGET)?;
+        Ok(ppm)
+    }
+
+    /// Stores temperature offset, sensor altitude and ASC settings in EEPROM so they
+    /// survive a power cycle. Only valid in idle mode.
+    ///
+    /// The EEPROM is rated for a limited number of write cycles, persist only when the
+    /// settings actually changed instead of on every boot.
+    pub fn persist_settings(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_PERSIST_SETTINGS)?;
+        self.delay.delay_ms(PERSIST_SETTINGS_DELAY_MS);
+        Ok(())
+    }
+
+    /// Applies and persists `settings` only if they differ from the current settings,
+    /// sparing EEPROM write cycles when called on every boot. Only valid in idle mode.
+    ///
+    /// Returns whether the settings were persisted.
+    pub fn persist_if_changed(
+        &mut self,
+        settings: &SCD41Settings,
+    ) -> Result<bool, SCD41Error<I::Error>> {
+        let mut changed = false;
+        let offset_ticks = temperature_offset_to_ticks(settings.temperature_offset);
+        let [current_offset_ticks] = self.read_words(CMD_GET_TEMPERATURE_OFFSET)?;
+        if current_offset_ticks != offset_ticks {
+            self.write_word(CMD_SET_TEMPERATURE_OFFSET, offset_ticks)?;
+            changed = true;
+        }
+        if self.get_sensor_altitude()? != settings.sensor_altitude {
+            self.set_sensor_altitude(settings.sensor_altitude)?;
+            changed = true;
+        }
+        if self.get_automatic_self_calibration_enabled()?
+            != settings.automatic_self_calibration_enabled
+        {
+            self.set_automatic_self_calibration_enabled(
+                settings.automatic_self_calibration_enabled,
+            )?;
+            changed = true;
+        }
+        if self.get_automatic_self_calibration_target()?
+            != settings.automatic_self_calibration_target
+        {
+            self.set_automatic_self_calibration_target(settings.automatic_self_calibration_target)?;
+            changed = true;
+        }
+        if changed {
+            self.persist_settings()?;
+        }
+        Ok(changed)
+    }
+
+    /// Reads the unique 48-bit serial number of the sensor. Only valid in idle mode.
+    pub fn get_serial_number(&mut self) -> Result<u64, SCD41Error<I::Error>> {
+        let words = self.read_words(CMD_GET_SERIAL_NUMBER)?;
+        Ok(decode_serial_number(words))
+    }
+
+    /// Runs the built-in self test. Only valid in idle mode.
+    ///
+    /// The test takes 10 seconds during which the sensor is not accessible. Returns
+    /// `SCD41Error::SelfTestFailed` with the reported malfunction word if the test fails.
+    pub fn perform_self_test(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.perform_self_test_with(|| {})
+    }
+
+    /// Runs the built-in self test like [`Self::perform_self_test`], calling `on_wait`
+    /// every 100 ms while waiting, e.g. to feed a watchdog.
+    pub fn perform_self_test_with(
+        &mut self,
+        mut on_wait: impl FnMut(),
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_PERFORM_SELF_TEST)?;
+        self.delay_ms_with(SELF_TEST_DELAY_MS, &mut on_wait);
+        let [result] = self.read_response()?;
+        decode_self_test(result)
+    }
+
+    /// Resets all user configuration stored in EEPROM (temperature offset, sensor
+    /// altitude, ASC settings) to factory defaults. Only valid in idle mode.
+    pub fn perform_factory_reset(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.perform_factory_reset_with(|| {})
+    }
+
+    /// Resets the settings like [`Self::perform_factory_reset`], calling `on_wait` every
+    /// 100 ms while waiting, e.g. to feed a watchdog.
+    pub fn perform_factory_reset_with(
+        &mut self,
+        mut on_wait: impl FnMut(),
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_PERFORM_FACTORY_RESET)?;
+        self.delay_ms_with(FACTORY_RESET_DELAY_MS, &mut on_wait);
+        Ok(())
+    }
+
+    /// Reloads the settings stored in EEPROM into the working registers, making
+    /// persisted settings effective without a power cycle. Only valid in idle mode.
+    pub fn reinit(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_REINIT)?;
+        self.delay.delay_ms(REINIT_DELAY_MS);
+        Ok(())
+    }
+
+    /// Puts the sensor from idle mode into sleep mode. Supported by the SCD41 only.
+    pub fn power_down(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_command(CMD_POWER_DOWN)?;
+        self.delay.delay_ms(WRITE_DELAY_MS);
+        Ok(())
+    }
+
+    /// Wakes the sensor up from sleep mode into idle mode. Supported by the SCD41 only.
+    ///
+    /// The sensor does not acknowledge the wake-up command, so a missing acknowledge is
+    /// ignored, other bus errors are returned. Use `get_serial_number()` to verify the
+    /// sensor is awake.
+    pub fn wake_up(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_wake_up()
+    }
+
+    /// Reads the sensor variant. Only valid in idle mode.
+    ///
+    /// The detected variant is remembered, afterwards commands that are only implemented
+    /// by the SCD41 return `SCD41Error::Unsupported` when talking to an SCD40.
+    pub fn get_sensor_variant(&mut self) -> Result<SensorVariant, SCD41Error<I::Error>> {
+        let [word] = self.read_words(CMD_GET_SENSOR_VARIANT)?;
+        let variant = decode_variant(word);
+        self.variant = Some(variant.clone());
+        Ok(variant)
+    }
+
+    /// Sends the wake-up command, ignoring the missing acknowledge, and waits until the
+    /// sensor is idle.
+    fn send_wake_up(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        match self.send_command(CMD_WAKE_UP) {
+            Err(error) if !is_nack(&error) => return Err(error),
+            _ => {}
+        }
+        self.delay.delay_ms(WAKE_UP_DELAY_MS);
+        Ok(())
+    }
+
+    fn read_measurement(&mut self) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        let (co2, temperature, humidity) = self.read_raw_ticks()?;
+        Ok(SCD41Response::from_ticks(co2, temperature, humidity))
+    }
+
+    fn send_command(&mut self, command: u16) -> Result<(), SCD41Error<I::Error>> {
+        self.i2c_write(&command.to_be_bytes())
+    }
+
+    /// Sends a command followed by a CRC-protected argument word.
+    fn write_word(&mut self, command: u16, word: u16) -> Result<(), SCD41Error<I::Error>> {
+        self.i2c_write(&encode_word(command, word))?;
+        self.delay.delay_ms(WRITE_DELAY_MS);
+        Ok(())
+    }
+
+    /// Sends a read command and returns its CRC-checked response words.
+    fn read_words<const N: usize>(
+        &mut self,
+        command: u16,
+    ) -> Result<[u16; N], SCD41Error<I::Error>> {
+        self.send_command(command)?;
+        self.delay.delay_ms(READ_DELAY_MS);
+        self.read_response()
+    }
+
+    /// Reads the CRC-checked response words of a previously sent command.
+    fn read_response<const N: usize>(&mut self) -> Result<[u16; N], SCD41Error<I::Error>> {
+        let mut buf = [0u8; MAX_RESPONSE_LEN];
+        let buf = &mut buf[..N * 3];
+        self.i2c_read(buf)?;
+        decode_words(buf)
+    }
+
+    /// Waits `millis` in steps of at most [`WAIT_CALLBACK_INTERVAL_MS`], calling `on_wait`
+    /// before every step.
+    fn delay_ms_with(&mut self, millis: u32, on_wait: &mut impl FnMut()) {
+        let mut remaining_ms = millis;
+        while remaining_ms > 0 {
+            on_wait();
+            let step_ms = remaining_ms.min(WAIT_CALLBACK_INTERVAL_MS);
+            self.delay.delay_ms(step_ms);
+            remaining_ms -= step_ms;
+        }
+    }
+
+    fn i2c_read(&mut self, read: &mut [u8]) -> Result<(), SCD41Error<I::Error>> {
+        match self.i2c.read(self.address, read) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(SCD41Error::I2CError(e)),
+        }
+    }
+
+    fn i2c_write(&mut self, write: &[u8]) -> Result<(), SCD41Error<I::Error>> {
+        match self.i2c.write(self.address, write) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(SCD41Error::I2CError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDelay, MockI2c, Transaction};
+
+    #[test]
+    fn read_decodes_measurement() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        let response = sensor.read().unwrap();
+        assert_eq!(response.co2, 500f32);
+        assert!((response.temperature - 25f32).abs() < 0.01);
+        assert!((response.humidity - 37f32).abs() < 0.01);
+        i2c.done();
+    }
+
+    #[test]
+    fn wake_up_tolerates_missing_acknowledge() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [Transaction::WriteNack(CMD_WAKE_UP.to_be_bytes().to_vec())],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        assert_eq!(sensor.wake_up(), Ok(()));
+        i2c.done();
+    }
+
+    #[test]
+    fn setup_tolerates_unsupported_variant_command() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::WriteNack(CMD_WAKE_UP.to_be_bytes().to_vec()),
+                Transaction::command(CMD_STOP_PERIODIC_MEASUREMENT),
+                Transaction::command(CMD_REINIT),
+                Transaction::command(CMD_GET_SERIAL_NUMBER),
+                Transaction::words(&[0x1234, 0x5678, 0x9abc]),
+                Transaction::WriteNack(CMD_GET_SENSOR_VARIANT.to_be_bytes().to_vec()),
+            ],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        sensor.setup().unwrap();
+        assert_eq!(sensor.variant, None);
+        i2c.done();
+    }
+
+    #[test]
+    fn read_without_new_sample_returns_no_data() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::ReadNack,
+            ],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        assert!(matches!(sensor.read(), Err(SCD41Error::NoData)));
+        i2c.done();
+    }
+
+    #[test]
+    fn read_retry_retries_transient_bus_errors() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::WriteArbitrationLoss(CMD_READ_MEASUREMENT.to_be_bytes().to_vec()),
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        let response = sensor.read_retry(3).unwrap();
+        assert_eq!(response.co2, 500f32);
+        i2c.done();
+    }
+
+    #[test]
+    fn setup_wakes_sensor_first() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::WriteNack(CMD_WAKE_UP.to_be_bytes().to_vec()),
+                Transaction::command(CMD_STOP_PERIODIC_MEASUREMENT),
+                Transaction::command(CMD_REINIT),
+                Transaction::command(CMD_GET_SERIAL_NUMBER),
+                Transaction::words(&[0x1234, 0x5678, 0x9abc]),
+                Transaction::command(CMD_GET_SENSOR_VARIANT),
+                Transaction::words(&[0x1440]),
+            ],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        sensor.setup().unwrap();
+        assert_eq!(sensor.variant, Some(SensorVariant::Scd41));
+        i2c.done();
+    }
+
+    #[test]
+    fn stale_read_guard_skips_read_without_new_sample() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8000]),
+            ],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        sensor.set_stale_read_guard(true);
+        assert!(matches!(sensor.read(), Err(SCD41Error::NoData)));
+        i2c.done();
+    }
+
+    #[test]
+    fn stale_read_guard_reads_new_sample() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8006]),
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor = SCD41SensorBlocking::new_default(&mut i2c, MockDelay);
+        sensor.set_stale_read_guard(true);
+        assert_eq!(sensor.read().unwrap().co2, 500f32);
+        i2c.done();
+    }
+}
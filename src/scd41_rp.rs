@@ -1,110 +1,1104 @@
-use crate::{SCD41Error, SCD41Response};
-use embassy_time::Duration;
+use crate::protocol::*;
+use crate::{
+    AsyncSensor, SCD41Error, SCD41Response, SCD41Settings, SelfHeatingEstimator, SensorVariant,
+};
+use core::future::{Future, poll_fn};
+use core::pin::pin;
+use core::task::Poll;
+use embedded_hal_async::delay::DelayNs;
 use embedded_hal_async::i2c::I2c;
 
-pub struct SCD41Sensor {
+/// SCD41 driver on any bus implementing the async embedded-hal `I2c` trait.
+///
+/// Waits for command execution through the given async `DelayNs`, with the
+/// `embassy-time` feature [`SCD41Sensor::new`] uses `embassy_time::Delay`.
+///
+/// Takes either an exclusive `&mut` reference to the bus or a device handle of a shared
+/// bus, such as `embassy_embedded_hal::shared_bus::asynch::i2c::I2cDevice`. A shared bus
+/// guarded by a `Mutex` has to outlive all its devices, which typically means declaring
+/// it `'static` (e.g. with `static_cell::StaticCell`) when devices move into tasks.
+///
+/// Dropping a future (e.g. on a `select!` timeout) leaves the driver usable and never
+/// misaligns responses, as every command is sent and its response read within one call.
+/// A single shot measurement dropped before its result was read keeps the sensor busy
+/// for up to 5 seconds, the next command first waits until the sample is ready, which
+/// is then returned by the next read. Other commands dropped during their execution
+/// time, such as [`SCD41Sensor::perform_self_test`], are not waited for, the sensor
+/// stays busy and the next command may fail with `SCD41Error::I2CError`.
+pub struct SCD41Sensor<I: I2c, D: DelayNs> {
+    i2c: I,
+    delay: D,
     address: u8,
-    initialization_step: InitializationStep,
-    last_response: Option<SCD41Response>,
-    next_step_time: embassy_time::Instant,
+    variant: Option<SensorVariant>,
+    retry_delay_ms: u32,
+    stale_read_guard: bool,
+    discard_warmup_samples: u8,
+    samples_since_start: u32,
+    data_ready_timeout_ms: u32,
+    measurement_pending: bool,
+    transfer_timeout_ms: Option<u32>,
 }
 
-impl SCD41Sensor {
-    pub fn new(address: u8) -> Self {
+#[cfg(feature = "embassy-time")]
+impl<I: I2c> SCD41Sensor<I, embassy_time::Delay> {
+    /// Creates a sensor on the given bus timed by `embassy_time`, the SCD41 listens on
+    /// [`SCD41_DEFAULT_ADDRESS`].
+    pub fn new(i2c: I, address: u8) -> Self {
+        Self::new_with_delay(i2c, embassy_time::Delay, address)
+    }
+
+    /// Creates a sensor on the given bus timed by `embassy_time` at
+    /// [`SCD41_DEFAULT_ADDRESS`].
+    pub fn new_default(i2c: I) -> Self {
+        Self::new(i2c, SCD41_DEFAULT_ADDRESS)
+    }
+}
+
+impl<I: I2c, D: DelayNs> SCD41Sensor<I, D> {
+    /// Creates a sensor on the given bus timed by `delay`, the SCD41 listens on
+    /// [`SCD41_DEFAULT_ADDRESS`].
+    pub fn new_with_delay(i2c: I, delay: D, address: u8) -> Self {
         Self {
+            i2c,
+            delay,
             address,
-            initialization_step: InitializationStep::Initial,
-            last_response: None,
-            next_step_time: embassy_time::Instant::now(),
+            variant: None,
+            retry_delay_ms: DEFAULT_RETRY_DELAY_MS,
+            stale_read_guard: false,
+            discard_warmup_samples: 0,
+            samples_since_start: 0,
+            data_ready_timeout_ms: DATA_READY_TIMEOUT_MS,
+            measurement_pending: false,
+            transfer_timeout_ms: None,
+        }
+    }
+
+    /// Brings the sensor into a known idle state and verifies it is present.
+    ///
+    /// Wakes the sensor in case [`Self::power_down`] left it in sleep mode before a reset
+    /// of the host, ignoring a missing acknowledge like [`Self::wake_up`]. Then stops a
+    /// measurement left running from a previous boot, reloads the settings from EEPROM,
+    /// reads the serial number and detects the sensor variant. Returns
+    /// `SCD41Error::NoData` if the sensor does not respond.
+    ///
+    /// Variant detection is best-effort: firmware that does not acknowledge the variant
+    /// command leaves the variant unknown, and SCD41-only commands are then not refused.
+    pub async fn setup(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_wake_up().await.map_err(no_response)?;
+        self.stop_periodic_measurement()
+            .await
+            .map_err(no_response)?;
+        self.reinit().await?;
+        self.get_serial_number().await.map_err(no_response)?;
+        match self.get_sensor_variant().await {
+            Err(error) if !is_nack(&error) => Err(error),
+            _ => Ok(()),
         }
     }
 
-    pub async fn read<'d, I: embassy_rp::i2c::Instance>(
+    /// Starts periodic measurement and waits until the first sample is available.
+    ///
+    /// The sensor updates the measurement every 5 seconds afterwards.
+    pub async fn start_periodic_measurement(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_START_PERIODIC_MEASUREMENT).await?;
+        self.samples_since_start = 0;
+        self.data_ready_timeout_ms = DATA_READY_TIMEOUT_MS;
+        self.delay.delay_ms(FIRST_SAMPLE_DELAY_MS).await;
+        Ok(())
+    }
+
+    /// Starts low power periodic measurement and waits until the first sample is available.
+    ///
+    /// The sensor updates the measurement every 30 seconds instead of 5, drawing about
+    /// 3.2 mA on average instead of 15 mA at 3.3 V. Wait for samples with
+    /// [`LOW_POWER_DATA_READY_TIMEOUT_MS`].
+    pub async fn start_low_power_periodic_measurement(
         &mut self,
-        i2c: &mut embassy_rp::i2c::I2c<'d, I, embassy_rp::i2c::Async>,
-    ) -> Result<SCD41Response, SCD41Error> {
-        let now = embassy_time::Instant::now();
-        if now < self.next_step_time {
-            return if let Some(response) = &self.last_response {
-                Ok(response.clone())
-            } else {
-                Err(SCD41Error::NoData)
-            };
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_START_LOW_POWER_PERIODIC_MEASUREMENT)
+            .await?;
+        self.samples_since_start = 0;
+        self.data_ready_timeout_ms = LOW_POWER_DATA_READY_TIMEOUT_MS;
+        self.delay.delay_ms(LOW_POWER_FIRST_SAMPLE_DELAY_MS).await;
+        Ok(())
+    }
+
+    /// Stops periodic measurement and waits until the sensor accepts new commands.
+    ///
+    /// Safe to call when measurement was never started.
+    pub async fn stop_periodic_measurement(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_STOP_PERIODIC_MEASUREMENT).await?;
+        self.delay.delay_ms(STOP_DELAY_MS).await;
+        Ok(())
+    }
+
+    /// Reads the latest measurement taken in periodic mode.
+    ///
+    /// The sensor empties its buffer on read-out and does not acknowledge reads until
+    /// the next sample is taken, reading again before then returns
+    /// `SCD41Error::NoData`.
+    ///
+    /// Unlike [`AsyncSensor::read`] for this sensor, which waits for the next sample like
+    /// [`Self::read_when_ready`], this reads without waiting. Method call syntax
+    /// `sensor.read()` resolves to this inherent method, generic code bound on
+    /// [`AsyncSensor`] gets the waiting one.
+    pub async fn read(&mut self) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        if self.stale_read_guard && !self.get_data_ready_status().await? {
+            return Err(SCD41Error::NoData);
         }
-        match self.initialization_step {
-            InitializationStep::Initial => {
-                self.initialization_step = InitializationStep::StopMeasurement;
-                self.next_step_time = now + Duration::from_millis(1000);
-                Err(SCD41Error::NoData)
-            }
-            InitializationStep::StopMeasurement => {
-                self.initialization_step = InitializationStep::Reinit;
-                self.next_step_time = now + Duration::from_millis(1000);
-                self.i2c_write(i2c, &[0x3f, 0x86]).await?;
-                Err(SCD41Error::NoData)
+        self.read_measurement().await
+    }
+
+    /// Enables or disables the check of [`Self::read`] for a new sample, disabled by
+    /// default.
+    ///
+    /// Without the check an empty measurement buffer is detected from the sensor not
+    /// acknowledging the read, which relies on the bus reporting
+    /// `ErrorKind::NoAcknowledge`. With it, [`Self::read`] queries the data-ready status
+    /// first and returns `SCD41Error::NoData` without reading, for buses reporting a
+    /// missing acknowledge as another error kind, at the cost of a query per read.
+    pub fn set_stale_read_guard(&mut self, enabled: bool) {
+        self.stale_read_guard = enabled;
+    }
+
+    /// Reads the latest measurement as CRC-checked raw `(co2, temperature, humidity)`
+    /// ticks without floating point conversion.
+    ///
+    /// CO2 ticks are ppm, convert the others with
+    /// [`tick_to_temperature`](crate::tick_to_temperature) and
+    /// [`tick_to_humidity`](crate::tick_to_humidity).
+    /// Returns `SCD41Error::NoData` if no new sample is available, like [`Self::read`].
+    pub async fn read_raw_ticks(&mut self) -> Result<(u16, u16, u16), SCD41Error<I::Error>> {
+        let [co2, temperature, humidity] = self
+            .read_words(CMD_READ_MEASUREMENT)
+            .await
+            .map_err(no_sample)?;
+        Ok((co2, temperature, humidity))
+    }
+
+    /// Reads the latest measurement, making up to `attempts` attempts on bus errors.
+    ///
+    /// Only `SCD41Error::I2CError` is retried, after waiting the retry delay, other errors
+    /// such as `SCD41Error::CrcMismatch` are returned immediately. `SCD41Error::NoData`
+    /// for a read before a new sample is available is not retried either, wait for the
+    /// sample with [`Self::read_when_ready`] instead. Returns the last bus error if all
+    /// attempts fail. The read is attempted at least once.
+    pub async fn read_retry(
+        &mut self,
+        attempts: u8,
+    ) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        let mut attempt = 1;
+        loop {
+            match self.read().await {
+                Err(SCD41Error::I2CError(_)) if attempt < attempts => {
+                    attempt += 1;
+                    self.delay.delay_ms(self.retry_delay_ms).await;
+                }
+                result => return result,
             }
-            InitializationStep::Reinit => {
-                self.initialization_step = InitializationStep::StartMeasurement;
-                self.next_step_time = now + Duration::from_millis(1000);
-                self.i2c_write(i2c, &[0x36, 0x46]).await?;
-                Err(SCD41Error::NoData)
+        }
+    }
+
+    /// Sets the delay between attempts of [`Self::read_retry`], 10 ms by default.
+    pub fn set_retry_delay_ms(&mut self, millis: u32) {
+        self.retry_delay_ms = millis;
+    }
+
+    /// Limits every bus transfer to `millis`, or lifts the limit with `None`, the default.
+    ///
+    /// A transfer exceeding the limit, e.g. on a bus held low or a host waiting on clock
+    /// stretching, is abandoned with `SCD41Error::Timeout` instead of blocking the task.
+    ///
+    /// No command of the SCD4x is stretch-heavy: the sensor does not stretch the clock
+    /// but relies on fixed execution times, which are waited by delays before the
+    /// response is read and are not affected by the limit. Set it where a transfer may
+    /// never complete, e.g. on an RP2040, whose async I2C driver has no timeout of its
+    /// own, sharing the bus with other devices or powering the sensor separately.
+    pub fn set_transfer_timeout_ms(&mut self, millis: Option<u32>) {
+        self.transfer_timeout_ms = millis;
+    }
+
+    /// Performs an on-demand measurement from idle mode and reads it.
+    ///
+    /// The measurement takes 5 seconds. Supported by the SCD41 only, see
+    /// [`SCD41Sensor::get_sensor_variant`].
+    pub async fn measure_single_shot(&mut self) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_command(CMD_MEASURE_SINGLE_SHOT).await?;
+        self.measurement_pending = true;
+        self.delay.delay_ms(SINGLE_SHOT_DELAY_MS).await;
+        self.measurement_pending = false;
+        self.read_measurement().await
+    }
+
+    /// Performs an on-demand temperature and humidity measurement from idle mode and
+    /// returns `(temperature, humidity)`, CO2 is not measured.
+    ///
+    /// The measurement takes 50 ms. Supported by the SCD41 only, see
+    /// [`SCD41Sensor::get_sensor_variant`].
+    pub async fn measure_single_shot_rht_only(
+        &mut self,
+    ) -> Result<(f32, f32), SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_command(CMD_MEASURE_SINGLE_SHOT_RHT_ONLY).await?;
+        self.measurement_pending = true;
+        self.delay.delay_ms(SINGLE_SHOT_RHT_ONLY_DELAY_MS).await;
+        self.measurement_pending = false;
+        let response = self.read_measurement().await?;
+        Ok((response.temperature, response.humidity))
+    }
+
+    /// Waits until a new sample is available and reads it.
+    ///
+    /// Returns `SCD41Error::Timeout` if no sample becomes ready within `timeout_ms`,
+    /// see [`DATA_READY_TIMEOUT_MS`] and [`LOW_POWER_DATA_READY_TIMEOUT_MS`] for the values
+    /// matching periodic measurement. Samples within the warm-up period set with
+    /// [`Self::set_discard_warmup_samples`] are read and discarded, the timeout applies
+    /// to every sample.
+    pub async fn read_when_ready(
+        &mut self,
+        timeout_ms: u32,
+    ) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        loop {
+            let mut waited_ms = 0;
+            while !self.get_data_ready_status().await? {
+                if waited_ms >= timeout_ms {
+                    return Err(SCD41Error::Timeout);
+                }
+                self.delay.delay_ms(DATA_READY_POLL_INTERVAL_MS).await;
+                waited_ms += DATA_READY_POLL_INTERVAL_MS;
             }
-            InitializationStep::StartMeasurement => {
-                self.initialization_step = InitializationStep::ReadData;
-                self.next_step_time = now + Duration::from_millis(1000);
-                self.i2c_write(i2c, &[0x21, 0xb1]).await?;
-                Err(SCD41Error::NoData)
+            let response = self.read_measurement().await?;
+            self.samples_since_start = self.samples_since_start.saturating_add(1);
+            if self.samples_since_start > self.discard_warmup_samples as u32 {
+                return Ok(response);
             }
-            InitializationStep::ReadData => {
-                self.next_step_time = now + Duration::from_millis(5000);
-                let mut buf = [0u8; 9];
-                self.i2c_write_read(i2c, &[0xe4, 0xb8], &mut buf).await?;
-                if buf[1] == 0x0 {
-                    self.next_step_time = now + Duration::from_millis(1000);
-                    Err(SCD41Error::NoData)
-                } else {
-                    self.i2c_write_read(i2c, &[0xec, 0x05], &mut buf).await?;
-                    let delimiter = 0xffff as f32;
-                    let co2_ppm = i16::from_be_bytes([buf[0], buf[1]]) as f32;
-                    let temperature_data = i16::from_be_bytes([buf[3], buf[4]]) as f32;
-                    let temperature = -45f32 + 175f32 * temperature_data / delimiter;
-                    let humidity_data = i16::from_be_bytes([buf[6], buf[7]]) as f32;
-                    let humidity = 100f32 * (humidity_data / delimiter);
-                    let response = SCD41Response {
-                        co2: co2_ppm,
-                        temperature,
-                        humidity,
-                    };
-                    self.last_response = Some(response.clone());
-                    Ok(response)
-                }
+        }
+    }
+
+    /// Sets the number of samples [`Self::read_when_ready`] and [`Self::try_read`]
+    /// discard after a start of periodic measurement, 0 by default.
+    ///
+    /// The first readings after start-up are less accurate while the sensor conditions.
+    pub fn set_discard_warmup_samples(&mut self, samples: u8) {
+        self.discard_warmup_samples = samples;
+    }
+
+    /// Returns the number of samples read by [`Self::read_when_ready`] and
+    /// [`Self::try_read`] since periodic measurement was last started, including
+    /// discarded warm-up samples.
+    pub fn samples_since_start(&self) -> u32 {
+        self.samples_since_start
+    }
+
+    /// Returns an endless stream of fresh periodic measurements, each waited for like
+    /// [`AsyncSensor::read`]. Periodic measurement has to be started beforehand.
+    ///
+    /// The stream has to be pinned, e.g. with `core::pin::pin!`, before polling it.
+    /// Dropping it while a sample is awaited leaves periodic measurement running, the
+    /// pending sample is returned by the next read.
+    #[cfg(feature = "stream")]
+    pub fn measurement_stream(
+        &mut self,
+    ) -> impl futures_util::Stream<Item = Result<SCD41Response, SCD41Error<I::Error>>> + '_ {
+        futures_util::stream::unfold(self, |sensor| async move {
+            let timeout_ms = sensor.data_ready_timeout_ms;
+            Some((sensor.read_when_ready(timeout_ms).await, sensor))
+        })
+    }
+
+    /// Reads a new sample if one is available, without waiting.
+    ///
+    /// Returns `Ok(None)` if no sample became available since the last read, for polling
+    /// loops that must not block on data-ready. Samples within the warm-up period set
+    /// with [`Self::set_discard_warmup_samples`] are read and discarded, returning
+    /// `Ok(None)` as well.
+    pub async fn try_read(&mut self) -> Result<Option<SCD41Response>, SCD41Error<I::Error>> {
+        if !self.get_data_ready_status().await? {
+            return Ok(None);
+        }
+        let response = self.read_measurement().await?;
+        self.samples_since_start = self.samples_since_start.saturating_add(1);
+        if self.samples_since_start > self.discard_warmup_samples as u32 {
+            Ok(Some(response))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Checks whether the sensor acknowledges a data-ready status query, e.g. for a
+    /// health check of a sensor that may have been disconnected.
+    ///
+    /// Returns `Ok(false)` if the sensor does not acknowledge, which it also does not
+    /// while powered down or busy executing a command. Other bus errors are returned as
+    /// `SCD41Error::I2CError`.
+    pub async fn is_present(&mut self) -> Result<bool, SCD41Error<I::Error>> {
+        match self.get_data_ready_status().await {
+            Ok(_) => Ok(true),
+            Err(error) if is_nack(&error) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks whether a new sample is available since the last read.
+    pub async fn get_data_ready_status(&mut self) -> Result<bool, SCD41Error<I::Error>> {
+        let [status] = self.read_words(CMD_GET_DATA_READY_STATUS).await?;
+        Ok(is_data_ready(status))
+    }
+
+    /// Sets the temperature offset in °C used to compensate self-heating of the sensor.
+    ///
+    /// The offset is rounded to the sensor resolution of about 0.003 °C and clamped to
+    /// the supported range of 0 to 175 °C. Only valid in idle mode, the sensor does not
+    /// acknowledge the command during periodic measurement and `SCD41Error::I2CError`
+    /// is returned.
+    pub async fn set_temperature_offset(
+        &mut self,
+        celsius: f32,
+    ) -> Result<(), SCD41Error<I::Error>> {
+        let ticks = temperature_offset_to_ticks(celsius);
+        self.write_word(CMD_SET_TEMPERATURE_OFFSET, ticks).await
+    }
+
+    /// Gets the temperature offset in °C.
+    ///
+    /// Only valid in idle mode, the sensor does not acknowledge the command during
+    /// periodic measurement and `SCD41Error::I2CError` is returned.
+    pub async fn get_temperature_offset(&mut self) -> Result<f32, SCD41Error<I::Error>> {
+        let [ticks] = self.read_words(CMD_GET_TEMPERATURE_OFFSET).await?;
+        Ok(temperature_offset_from_ticks(ticks))
+    }
+
+    /// Sets the temperature offset estimated by `estimator` from readings taken with the
+    /// current offset, resets the estimator and returns the offset. Does nothing and
+    /// returns `None` before the first estimator update.
+    ///
+    /// Call it periodically, feeding the estimator between calls, to track the
+    /// self-heating over time. Only valid in idle mode, stop periodic measurement
+    /// beforehand. Persist the offset with [`Self::persist_settings`] to keep it across
+    /// power cycles.
+    pub async fn apply_self_heating_model(
+        &mut self,
+        estimator: &mut SelfHeatingEstimator,
+    ) -> Result<Option<f32>, SCD41Error<I::Error>> {
+        if estimator.deviation().is_none() {
+            return Ok(None);
+        }
+        let current_offset = self.get_temperature_offset().await?;
+        let offset = estimator.converged_offset(current_offset);
+        if let Some(offset) = offset {
+            self.set_temperature_offset(offset).await?;
+            estimator.reset();
+        }
+        Ok(offset)
+    }
+
+    /// Sets the sensor altitude in meters above sea level used for pressure compensation.
+    ///
+    /// Only valid in idle mode, the sensor does not acknowledge the command during
+    /// periodic measurement and `SCD41Error::I2CError` is returned.
+    pub async fn set_sensor_altitude(&mut self, meters: u16) -> Result<(), SCD41Error<I::Error>> {
+        self.write_word(CMD_SET_SENSOR_ALTITUDE, meters).await
+    }
+
+    /// Gets the sensor altitude in meters above sea level.
+    ///
+    /// Only valid in idle mode, the sensor does not acknowledge the command during
+    /// periodic measurement and `SCD41Error::I2CError` is returned.
+    pub async fn get_sensor_altitude(&mut self) -> Result<u16, SCD41Error<I::Error>> {
+        let [meters] = self.read_words(CMD_GET_SENSOR_ALTITUDE).await?;
+        Ok(meters)
+    }
+
+    /// Sets the ambient pressure in Pa used for pressure compensation.
+    ///
+    /// Overrides the sensor altitude and, unlike it, may be updated during periodic
+    /// measurement, so readings of an external barometer can be fed in continuously.
+    pub async fn set_ambient_pressure(&mut self, pascals: u32) -> Result<(), SCD41Error<I::Error>> {
+        let hectopascals = pascals_to_hectopascals(pascals);
+        self.write_word(CMD_SET_AMBIENT_PRESSURE, hectopascals)
+            .await
+    }
+
+    /// Performs forced recalibration against a known CO2 concentration and returns the
+    /// applied correction in ppm.
+    ///
+    /// The sensor must have been operated in periodic measurement for at least 3 minutes
+    /// in an environment with constant, known CO2 concentration, and periodic measurement
+    /// must be stopped before calling. Returns `SCD41Error::RecalibrationFailed` if the
+    /// sensor rejects the recalibration.
+    pub async fn perform_forced_recalibration(
+        &mut self,
+        target_ppm: u16,
+    ) -> Result<i16, SCD41Error<I::Error>> {
+        self.write_word(CMD_PERFORM_FORCED_RECALIBRATION, target_ppm)
+            .await?;
+        self.delay.delay_ms(FORCED_RECALIBRATION_DELAY_MS).await;
+        let [correction] = self.read_response().await?;
+        decode_recalibration(correction)
+    }
+
+    /// Enables or disables automatic self-calibration (ASC).
+    ///
+    /// ASC assumes the sensor is regularly exposed to fresh air, disable it in sealed
+    /// environments and use forced recalibration instead. Only valid in idle mode.
+    pub async fn set_automatic_self_calibration_enabled(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.write_word(CMD_SET_AUTOMATIC_SELF_CALIBRATION_ENABLED, enabled as u16)
+            .await
+    }
+
+    /// Checks whether automatic self-calibration (ASC) is enabled. Only valid in idle mode.
+    pub async fn get_automatic_self_calibration_enabled(
+        &mut self,
+    ) -> Result<bool, SCD41Error<I::Error>> {
+        let [enabled] = self
+            .read_words(CMD_GET_AUTOMATIC_SELF_CALIBRATION_ENABLED)
+            .await?;
+        Ok(enabled != 0)
+    }
+
+    /// Sets the CO2 concentration in ppm that automatic self-calibration (ASC) assumes
+    /// for fresh air, 400 ppm by default. Only valid in idle mode.
+    pub async fn set_automatic_self_calibration_target(
+        &mut self,
+        ppm: u16,
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.write_word(CMD_SET_AUTOMATIC_SELF_CALIBRATION_TARGET, ppm)
+            .await
+    }
+
+    /// Gets the CO2 concentration in ppm that automatic self-calibration (ASC) assumes
+    /// for fresh air. Only valid in idle mode.
+    pub async fn get_automatic_self_calibration_target(
+        &mut self,
+    ) -> Result<u16, SCD41Error<I::Error>> {
+        let [ppm] = self
+            .read_words(CMD_GET_AUTOMATIC_SELF_CALIBRATION_TARGET)
+            .await?;
+        Ok(ppm)
+    }
+
+    /// Stores temperature offset, sensor altitude and ASC settings in EEPROM so they
+    /// survive a power cycle. Only valid in idle mode.
+    ///
+    /// The EEPROM is rated for a limited number of write cycles, persist only when the
+    /// settings actually changed instead of on every boot.
+    pub async fn persist_settings(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_PERSIST_SETTINGS).await?;
+        self.delay.delay_ms(PERSIST_SETTINGS_DELAY_MS).await;
+        Ok(())
+    }
+
+    /// Applies and persists `settings` only if they differ from the current settings,
+    /// sparing EEPROM write cycles when called on every boot. Only valid in idle mode.
+    ///
+    /// Returns whether the settings were persisted.
+    pub async fn persist_if_changed(
+        &mut self,
+        settings: &SCD41Settings,
+    ) -> Result<bool, SCD41Error<I::Error>> {
+        let mut changed = false;
+        let offset_ticks = temperature_offset_to_ticks(settings.temperature_offset);
+        let [current_offset_ticks] = self.read_words(CMD_GET_TEMPERATURE_OFFSET).await?;
+        if current_offset_ticks != offset_ticks {
+            self.write_word(CMD_SET_TEMPERATURE_OFFSET, offset_ticks)
+                .await?;
+            changed = true;
+        }
+        if self.get_sensor_altitude().await? != settings.sensor_altitude {
+            self.set_sensor_altitude(settings.sensor_altitude).await?;
+            changed = true;
+        }
+        if self.get_automatic_self_calibration_enabled().await?
+            != settings.automatic_self_calibration_enabled
+        {
+            self.set_automatic_self_calibration_enabled(
+                settings.automatic_self_calibration_enabled,
+            )
+            .await?;
+            changed = true;
+        }
+        if self.get_automatic_self_calibration_target().await?
+            != settings.automatic_self_calibration_target
+        {
+            self.set_automatic_self_calibration_target(settings.automatic_self_calibration_target)
+                .await?;
+            changed = true;
+        }
+        if changed {
+            self.persist_settings().await?;
+        }
+        Ok(changed)
+    }
+
+    /// Reads the unique 48-bit serial number of the sensor. Only valid in idle mode.
+    pub async fn get_serial_number(&mut self) -> Result<u64, SCD41Error<I::Error>> {
+        let words = self.read_words(CMD_GET_SERIAL_NUMBER).await?;
+        Ok(decode_serial_number(words))
+    }
+
+    /// Runs the built-in self test. Only valid in idle mode.
+    ///
+    /// The test takes 10 seconds during which the sensor is not accessible. Returns
+    /// `SCD41Error::SelfTestFailed` with the reported malfunction word if the test fails.
+    pub async fn perform_self_test(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.perform_self_test_with(|| {}).await
+    }
+
+    /// Runs the built-in self test like [`Self::perform_self_test`], calling `on_wait`
+    /// every 100 ms while waiting, e.g. to feed a watchdog.
+    pub async fn perform_self_test_with(
+        &mut self,
+        mut on_wait: impl FnMut(),
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_PERFORM_SELF_TEST).await?;
+        self.delay_ms_with(SELF_TEST_DELAY_MS, &mut on_wait).await;
+        let [result] = self.read_response().await?;
+        decode_self_test(result)
+    }
+
+    /// Resets all user configuration stored in EEPROM (temperature offset, sensor
+    /// altitude, ASC settings) to factory defaults. Only valid in idle mode.
+    pub async fn perform_factory_reset(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.perform_factory_reset_with(|| {}).await
+    }
+
+    /// Resets the settings like [`Self::perform_factory_reset`], calling `on_wait` every
+    /// 100 ms while waiting, e.g. to feed a watchdog.
+    pub async fn perform_factory_reset_with(
+        &mut self,
+        mut on_wait: impl FnMut(),
+    ) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_PERFORM_FACTORY_RESET).await?;
+        self.delay_ms_with(FACTORY_RESET_DELAY_MS, &mut on_wait)
+            .await;
+        Ok(())
+    }
+
+    /// Reloads the settings stored in EEPROM into the working registers, making
+    /// persisted settings effective without a power cycle. Only valid in idle mode.
+    pub async fn reinit(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        self.send_command(CMD_REINIT).await?;
+        self.delay.delay_ms(REINIT_DELAY_MS).await;
+        Ok(())
+    }
+
+    /// Puts the sensor from idle mode into sleep mode. Supported by the SCD41 only.
+    pub async fn power_down(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_command(CMD_POWER_DOWN).await?;
+        self.delay.delay_ms(WRITE_DELAY_MS).await;
+        Ok(())
+    }
+
+    /// Wakes the sensor up from sleep mode into idle mode. Supported by the SCD41 only.
+    ///
+    /// The sensor does not acknowledge the wake-up command, so a missing acknowledge is
+    /// ignored, other bus errors are returned. Use `get_serial_number()` to verify the
+    /// sensor is awake.
+    pub async fn wake_up(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        ensure_scd41(&self.variant)?;
+        self.send_wake_up().await
+    }
+
+    /// Reads the sensor variant. Only valid in idle mode.
+    ///
+    /// The detected variant is remembered, afterwards commands that are only implemented
+    /// by the SCD41 return `SCD41Error::Unsupported` when talking to an SCD40.
+    pub async fn get_sensor_variant(&mut self) -> Result<SensorVariant, SCD41Error<I::Error>> {
+        let [word] = self.read_words(CMD_GET_SENSOR_VARIANT).await?;
+        let variant = decode_variant(word);
+        self.variant = Some(variant.clone());
+        Ok(variant)
+    }
+
+    /// Sends the wake-up command, ignoring the missing acknowledge, and waits until the
+    /// sensor is idle.
+    async fn send_wake_up(&mut self) -> Result<(), SCD41Error<I::Error>> {
+        match self.send_command(CMD_WAKE_UP).await {
+            Err(error) if !is_nack(&error) => return Err(error),
+            _ => {}
+        }
+        self.delay.delay_ms(WAKE_UP_DELAY_MS).await;
+        Ok(())
+    }
+
+    async fn read_measurement(&mut self) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        let (co2, temperature, humidity) = self.read_raw_ticks().await?;
+        Ok(SCD41Response::from_ticks(co2, temperature, humidity))
+    }
+
+    async fn send_command(&mut self, command: u16) -> Result<(), SCD41Error<I::Error>> {
+        self.resync().await;
+        self.i2c_write(&command.to_be_bytes()).await
+    }
+
+    /// Sends a command followed by a CRC-protected argument word.
+    async fn write_word(&mut self, command: u16, word: u16) -> Result<(), SCD41Error<I::Error>> {
+        self.resync().await;
+        self.i2c_write(&encode_word(command, word)).await?;
+        self.delay.delay_ms(WRITE_DELAY_MS).await;
+        Ok(())
+    }
+
+    /// Sends a read command and returns its CRC-checked response words.
+    async fn read_words<const N: usize>(
+        &mut self,
+        command: u16,
+    ) -> Result<[u16; N], SCD41Error<I::Error>> {
+        self.send_command(command).await?;
+        self.delay.delay_ms(READ_DELAY_MS).await;
+        self.read_response().await
+    }
+
+    /// Reads the CRC-checked response words of a previously sent command.
+    async fn read_response<const N: usize>(&mut self) -> Result<[u16; N], SCD41Error<I::Error>> {
+        let mut buf = [0u8; MAX_RESPONSE_LEN];
+        let buf = &mut buf[..N * 3];
+        self.i2c_read(buf).await?;
+        decode_words(buf)
+    }
+
+    /// Waits for a single shot measurement whose future was dropped before completion,
+    /// the sensor does not accept commands while measuring.
+    ///
+    /// Polls the data-ready status, treating bus errors as busy, for at most the single
+    /// shot execution time.
+    async fn resync(&mut self) {
+        let mut waited_ms = 0;
+        while self.measurement_pending && waited_ms < SINGLE_SHOT_DELAY_MS {
+            if self.poll_data_ready().await.unwrap_or(false) {
+                break;
             }
+            self.delay.delay_ms(DATA_READY_POLL_INTERVAL_MS).await;
+            waited_ms += DATA_READY_POLL_INTERVAL_MS;
         }
+        self.measurement_pending = false;
     }
 
-    async fn i2c_write_read<'d, I : embassy_rp::i2c::Instance>(&mut self,
-        i2c: &mut embassy_rp::i2c::I2c<'d, I, embassy_rp::i2c::Async>,
-        write: &[u8], read: &mut [u8]) -> Result<(), SCD41Error> {
-        match i2c.write_read(self.address, write, read).await {
+    async fn poll_data_ready(&mut self) -> Result<bool, SCD41Error<I::Error>> {
+        self.i2c_write(&CMD_GET_DATA_READY_STATUS.to_be_bytes())
+            .await?;
+        self.delay.delay_ms(READ_DELAY_MS).await;
+        let [status] = self.read_response().await?;
+        Ok(is_data_ready(status))
+    }
+
+    /// Waits `millis` in steps of at most [`WAIT_CALLBACK_INTERVAL_MS`], calling `on_wait`
+    /// before every step.
+    async fn delay_ms_with(&mut self, millis: u32, on_wait: &mut impl FnMut()) {
+        let mut remaining_ms = millis;
+        while remaining_ms > 0 {
+            on_wait();
+            let step_ms = remaining_ms.min(WAIT_CALLBACK_INTERVAL_MS);
+            self.delay.delay_ms(step_ms).await;
+            remaining_ms -= step_ms;
+        }
+    }
+
+    async fn i2c_read(&mut self, read: &mut [u8]) -> Result<(), SCD41Error<I::Error>> {
+        let transfer = self.i2c.read(self.address, read);
+        let result = match self.transfer_timeout_ms {
+            Some(millis) => with_timeout(transfer, self.delay.delay_ms(millis))
+                .await
+                .ok_or(SCD41Error::Timeout)?,
+            None => transfer.await,
+        };
+        match result {
             Ok(_) => Ok(()),
-            Err(_) => Err(SCD41Error::I2CError),
+            Err(e) => Err(SCD41Error::I2CError(e)),
         }
     }
 
-    async fn i2c_write<'d, I : embassy_rp::i2c::Instance>(&mut self,
-        i2c: &mut embassy_rp::i2c::I2c<'d, I, embassy_rp::i2c::Async>,
-        write: &[u8]) -> Result<(), SCD41Error> {
-        match i2c.write(self.address, write).await {
+    async fn i2c_write(&mut self, write: &[u8]) -> Result<(), SCD41Error<I::Error>> {
+        let transfer = self.i2c.write(self.address, write);
+        let result = match self.transfer_timeout_ms {
+            Some(millis) => with_timeout(transfer, self.delay.delay_ms(millis))
+                .await
+                .ok_or(SCD41Error::Timeout)?,
+            None => transfer.await,
+        };
+        match result {
             Ok(_) => Ok(()),
-            Err(_) => Err(SCD41Error::I2CError),
+            Err(e) => Err(SCD41Error::I2CError(e)),
+        }
+    }
+}
+
+/// Runs `future` to completion, or returns `None` if `timeout` completes first.
+async fn with_timeout<F: Future>(future: F, timeout: impl Future) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut timeout = pin!(timeout);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            Poll::Ready(Some(output))
+        } else if timeout.as_mut().poll(cx).is_ready() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
         }
+    })
+    .await
+}
+
+/// Reading is the next fresh periodic measurement as an [`SCD41Response`], waiting up to
+/// [`DATA_READY_TIMEOUT_MS`], or [`LOW_POWER_DATA_READY_TIMEOUT_MS`] in low power
+/// periodic measurement, for it. Periodic measurement has to be started beforehand.
+///
+/// This differs from the inherent [`SCD41Sensor::read`], which does not wait.
+impl<I: I2c, D: DelayNs> AsyncSensor for SCD41Sensor<I, D> {
+    type Reading = SCD41Response;
+    type Error = SCD41Error<I::Error>;
+
+    async fn read(&mut self) -> Result<SCD41Response, SCD41Error<I::Error>> {
+        self.read_when_ready(self.data_ready_timeout_ms).await
     }
 }
 
-enum InitializationStep {
-    Initial,
-    StopMeasurement,
-    Reinit,
-    StartMeasurement,
-    ReadData,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDelay, MockI2c, Transaction};
+    use embassy_futures::block_on;
+
+    #[test]
+    fn read_decodes_measurement() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        let response = block_on(sensor.read()).unwrap();
+        assert_eq!(response.co2, 500f32);
+        assert!((response.temperature - 25f32).abs() < 0.01);
+        assert!((response.humidity - 37f32).abs() < 0.01);
+        i2c.done();
+    }
+
+    #[test]
+    fn set_temperature_offset_writes_checksummed_word() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [Transaction::Write(vec![0x24, 0x1d, 0x07, 0xe6, 0x48])],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        block_on(sensor.set_temperature_offset(5.4f32)).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn wake_up_tolerates_missing_acknowledge() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [Transaction::WriteNack(CMD_WAKE_UP.to_be_bytes().to_vec())],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        assert_eq!(block_on(sensor.wake_up()), Ok(()));
+        i2c.done();
+    }
+
+    #[test]
+    fn wake_up_returns_other_bus_errors() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [Transaction::WriteArbitrationLoss(
+                CMD_WAKE_UP.to_be_bytes().to_vec(),
+            )],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        assert!(matches!(
+            block_on(sensor.wake_up()),
+            Err(SCD41Error::I2CError(_))
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn persist_if_changed_skips_unchanged_settings() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_TEMPERATURE_OFFSET),
+                Transaction::words(&[0x0912]),
+                Transaction::command(CMD_GET_SENSOR_ALTITUDE),
+                Transaction::words(&[0]),
+                Transaction::command(CMD_GET_AUTOMATIC_SELF_CALIBRATION_ENABLED),
+                Transaction::words(&[1]),
+                Transaction::command(CMD_GET_AUTOMATIC_SELF_CALIBRATION_TARGET),
+                Transaction::words(&[400]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        let settings = SCD41Settings {
+            temperature_offset: 6.2f32,
+            ..SCD41Settings::default()
+        };
+        assert_eq!(block_on(sensor.persist_if_changed(&settings)), Ok(false));
+        i2c.done();
+    }
+
+    #[test]
+    fn persist_if_changed_writes_only_changed_setting() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_TEMPERATURE_OFFSET),
+                Transaction::words(&[0x05da]),
+                Transaction::command(CMD_GET_SENSOR_ALTITUDE),
+                Transaction::words(&[0]),
+                Transaction::Write(encode_word(CMD_SET_SENSOR_ALTITUDE, 350).to_vec()),
+                Transaction::command(CMD_GET_AUTOMATIC_SELF_CALIBRATION_ENABLED),
+                Transaction::words(&[1]),
+                Transaction::command(CMD_GET_AUTOMATIC_SELF_CALIBRATION_TARGET),
+                Transaction::words(&[400]),
+                Transaction::command(CMD_PERSIST_SETTINGS),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        let settings = SCD41Settings {
+            sensor_altitude: 350,
+            ..SCD41Settings::default()
+        };
+        assert_eq!(block_on(sensor.persist_if_changed(&settings)), Ok(true));
+        i2c.done();
+    }
+
+    #[test]
+    fn async_sensor_read_waits_for_new_sample() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8000]),
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8006]),
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        let response = block_on(AsyncSensor::read(&mut sensor)).unwrap();
+        assert_eq!(response.co2, 500f32);
+        i2c.done();
+    }
+
+    #[test]
+    fn stalled_transfer_times_out() {
+        let mut sensor = SCD41Sensor::new_with_delay(crate::mock::StallingI2c, MockDelay, 0x62);
+        sensor.set_transfer_timeout_ms(Some(10));
+        assert!(matches!(block_on(sensor.read()), Err(SCD41Error::Timeout)));
+    }
+
+    #[test]
+    fn apply_self_heating_model_applies_deviation_once() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_TEMPERATURE_OFFSET),
+                Transaction::words(&[0]),
+                Transaction::Write(encode_word(CMD_SET_TEMPERATURE_OFFSET, 0x02ed).to_vec()),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        let mut estimator = SelfHeatingEstimator::new(1f32);
+        estimator.update(&crate::mock::response(500f32, 27f32, 40f32), 25f32);
+        assert_eq!(
+            block_on(sensor.apply_self_heating_model(&mut estimator)),
+            Ok(Some(2f32))
+        );
+        assert_eq!(estimator.samples(), 0);
+        assert_eq!(
+            block_on(sensor.apply_self_heating_model(&mut estimator)),
+            Ok(None)
+        );
+        i2c.done();
+    }
+
+    #[test]
+    fn read_after_dropped_single_shot_waits_for_sample() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_MEASURE_SINGLE_SHOT),
+                Transaction::WriteNack(CMD_GET_DATA_READY_STATUS.to_be_bytes().to_vec()),
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8006]),
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor =
+            SCD41Sensor::new_with_delay(&mut i2c, crate::mock::StallingDelay::new(0), 0x62);
+        assert!(embassy_futures::poll_once(sensor.measure_single_shot()).is_pending());
+        let response = block_on(sensor.read()).unwrap();
+        assert_eq!(response.co2, 500f32);
+        i2c.done();
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn measurement_stream_yields_ready_samples() {
+        use futures_util::StreamExt;
+
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8000]),
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8006]),
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        {
+            let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+            let mut stream = core::pin::pin!(sensor.measurement_stream());
+            let response = block_on(stream.next()).unwrap().unwrap();
+            assert_eq!(response.co2, 500f32);
+        }
+        i2c.done();
+    }
+
+    #[test]
+    fn setup_tolerates_unsupported_variant_command() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::WriteNack(CMD_WAKE_UP.to_be_bytes().to_vec()),
+                Transaction::command(CMD_STOP_PERIODIC_MEASUREMENT),
+                Transaction::command(CMD_REINIT),
+                Transaction::command(CMD_GET_SERIAL_NUMBER),
+                Transaction::words(&[0x1234, 0x5678, 0x9abc]),
+                Transaction::WriteNack(CMD_GET_SENSOR_VARIANT.to_be_bytes().to_vec()),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        block_on(sensor.setup()).unwrap();
+        assert_eq!(sensor.variant, None);
+        i2c.done();
+    }
+
+    #[test]
+    fn read_without_new_sample_returns_no_data() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::ReadNack,
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        assert!(matches!(block_on(sensor.read()), Err(SCD41Error::NoData)));
+        i2c.done();
+    }
+
+    #[test]
+    fn read_retry_retries_transient_bus_errors() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::WriteArbitrationLoss(CMD_READ_MEASUREMENT.to_be_bytes().to_vec()),
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        let response = block_on(sensor.read_retry(3)).unwrap();
+        assert_eq!(response.co2, 500f32);
+        i2c.done();
+    }
+
+    #[cfg(not(feature = "skip-crc"))]
+    #[test]
+    fn read_retry_returns_crc_mismatch_without_retrying() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::Read(vec![0x01, 0xf4, 0x00, 0x66, 0x67, 0x00, 0x5e, 0xb9, 0x00]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        assert!(matches!(
+            block_on(sensor.read_retry(3)),
+            Err(SCD41Error::CrcMismatch)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn read_retry_returns_no_data_without_retrying() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::ReadNack,
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        assert!(matches!(
+            block_on(sensor.read_retry(3)),
+            Err(SCD41Error::NoData)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn setup_wakes_sensor_first() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::WriteNack(CMD_WAKE_UP.to_be_bytes().to_vec()),
+                Transaction::command(CMD_STOP_PERIODIC_MEASUREMENT),
+                Transaction::command(CMD_REINIT),
+                Transaction::command(CMD_GET_SERIAL_NUMBER),
+                Transaction::words(&[0x1234, 0x5678, 0x9abc]),
+                Transaction::command(CMD_GET_SENSOR_VARIANT),
+                Transaction::words(&[0x1440]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        block_on(sensor.setup()).unwrap();
+        assert_eq!(sensor.variant, Some(SensorVariant::Scd41));
+        i2c.done();
+    }
+
+    #[test]
+    fn stale_read_guard_skips_read_without_new_sample() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8000]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        sensor.set_stale_read_guard(true);
+        assert!(matches!(block_on(sensor.read()), Err(SCD41Error::NoData)));
+        i2c.done();
+    }
+
+    #[test]
+    fn stale_read_guard_reads_new_sample() {
+        let mut i2c = MockI2c::new(
+            SCD41_DEFAULT_ADDRESS,
+            [
+                Transaction::command(CMD_GET_DATA_READY_STATUS),
+                Transaction::words(&[0x8006]),
+                Transaction::command(CMD_READ_MEASUREMENT),
+                Transaction::words(&[0x01f4, 0x6667, 0x5eb9]),
+            ],
+        );
+        let mut sensor = SCD41Sensor::new_with_delay(&mut i2c, MockDelay, 0x62);
+        sensor.set_stale_read_guard(true);
+        assert_eq!(block_on(sensor.read()).unwrap().co2, 500f32);
+        i2c.done();
+    }
 }
@@ -0,0 +1,60 @@
+use crate::SCD41Response;
+
+/// Estimates the temperature offset compensating self-heating of the sensor from
+/// readings compared against a trusted ambient temperature reference.
+///
+/// Averages the deviation of the reported temperature from the reference with an
+/// exponential moving average. The estimate converges if the reference measures the
+/// same air in thermal equilibrium, the measurement mode does not change, as self-heating
+/// differs between modes, and the sensor offset is not changed while estimating.
+pub struct SelfHeatingEstimator {
+    weight: f32,
+    deviation: Option<f32>,
+    samples: u32,
+}
+
+impl SelfHeatingEstimator {
+    /// Creates an estimator weighting every new deviation with `weight`, between 0 and 1,
+    /// e.g. 0.05 to average over roughly the last 20 readings.
+    pub fn new(weight: f32) -> Self {
+        Self {
+            weight: weight.clamp(0f32, 1f32),
+            deviation: None,
+            samples: 0,
+        }
+    }
+
+    /// Accounts for a reading and the reference temperature in °C measured at the same
+    /// time.
+    pub fn update(&mut self, reading: &SCD41Response, ambient_reference: f32) {
+        let deviation = reading.temperature - ambient_reference;
+        let average = self.deviation.get_or_insert(deviation);
+        *average += self.weight * (deviation - *average);
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    /// Average deviation in °C of the reported temperature from the reference, `None`
+    /// before the first update.
+    pub fn deviation(&self) -> Option<f32> {
+        self.deviation
+    }
+
+    /// Number of readings accounted for.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Forgets all readings, starting a new estimate.
+    pub fn reset(&mut self) {
+        self.deviation = None;
+        self.samples = 0;
+    }
+
+    /// Temperature offset in °C compensating the average deviation, given the offset
+    /// `current_offset` that was applied while estimating. Never negative, as the sensor
+    /// does not support negative offsets.
+    pub fn converged_offset(&self, current_offset: f32) -> Option<f32> {
+        self.deviation
+            .map(|deviation| (current_offset + deviation).max(0f32))
+    }
+}
@@ -0,0 +1,13 @@
+use core::future::Future;
+
+/// Environmental sensor that can be read asynchronously, for application code that is
+/// generic over which sensor is present.
+pub trait AsyncSensor {
+    /// Measurement produced by the sensor.
+    type Reading;
+    /// Error returned when reading fails.
+    type Error;
+
+    /// Reads the next measurement.
+    fn read(&mut self) -> impl Future<Output = Result<Self::Reading, Self::Error>>;
+}
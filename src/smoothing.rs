@@ -0,0 +1,106 @@
+use crate::{AsyncSensor, Measurement};
+
+/// Wraps a sensor and returns the field-wise mean of its last `N` readings.
+///
+/// Until `N` readings have been taken the mean covers the readings available so far.
+/// `N` must be at least 1, which is checked at compile time.
+pub struct SmoothedReader<S: AsyncSensor, const N: usize> {
+    sensor: S,
+    readings: [Option<S::Reading>; N],
+    next: usize,
+}
+
+impl<S: AsyncSensor, const N: usize> SmoothedReader<S, N>
+where
+    S::Reading: Measurement,
+{
+    pub fn new(sensor: S) -> Self {
+        const { assert!(N > 0, "N must be at least 1") };
+        Self {
+            sensor,
+            readings: core::array::from_fn(|_| None),
+            next: 0,
+        }
+    }
+
+    /// Gives access to the wrapped sensor, e.g. to start measurement.
+    pub fn sensor(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    /// Reads the sensor and returns the mean of the buffered readings.
+    pub async fn read(&mut self) -> Result<S::Reading, S::Error> {
+        let reading = self.sensor.read().await?;
+        self.readings[self.next] = Some(reading.clone());
+        self.next = (self.next + 1) % N;
+
+        let mut mean = reading;
+        for index in 0..S::Reading::FIELDS {
+            let (sum, count) = self
+                .readings
+                .iter()
+                .flatten()
+                .fold((0f32, 0u32), |(sum, count), reading| {
+                    (sum + reading.field(index), count + 1)
+                });
+            mean.set_field(index, sum / count as f32);
+        }
+        Ok(mean)
+    }
+}
+
+impl<S: AsyncSensor, const N: usize> AsyncSensor for SmoothedReader<S, N>
+where
+    S::Reading: Measurement,
+{
+    type Reading = S::Reading;
+    type Error = S::Error;
+
+    async fn read(&mut self) -> Result<S::Reading, S::Error> {
+        SmoothedReader::read(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockSensor, response};
+    use embassy_futures::block_on;
+
+    #[test]
+    fn averages_available_readings_until_window_is_full() {
+        let sensor = MockSensor::new([
+            response(400f32, 20f32, 40f32),
+            response(500f32, 22f32, 44f32),
+            response(600f32, 24f32, 48f32),
+        ]);
+        let mut reader = SmoothedReader::<_, 4>::new(sensor);
+        assert_eq!(block_on(reader.read()).unwrap().co2, 400f32);
+        let mean = block_on(reader.read()).unwrap();
+        assert_eq!(mean.co2, 450f32);
+        assert_eq!(mean.temperature, 21f32);
+        assert_eq!(mean.humidity, 42f32);
+        let mean = block_on(reader.read()).unwrap();
+        assert_eq!(mean.co2, 500f32);
+        assert_eq!(mean.temperature, 22f32);
+        assert_eq!(mean.humidity, 44f32);
+    }
+
+    #[test]
+    fn drops_oldest_reading_from_full_window() {
+        let sensor = MockSensor::new([
+            response(400f32, 20f32, 40f32),
+            response(500f32, 22f32, 44f32),
+            response(600f32, 24f32, 48f32),
+            response(700f32, 26f32, 52f32),
+        ]);
+        let mut reader = SmoothedReader::<_, 3>::new(sensor);
+        for _ in 0..3 {
+            block_on(reader.read()).unwrap();
+        }
+        let mean = block_on(reader.read()).unwrap();
+        assert_eq!(mean.co2, 600f32);
+        assert_eq!(mean.temperature, 24f32);
+        assert_eq!(mean.humidity, 48f32);
+    }
+}
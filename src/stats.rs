@@ -0,0 +1,61 @@
+use crate::Measurement;
+
+/// Tracks the field-wise minimum and maximum and the most recent of a stream of
+/// readings, e.g. for daily high/low displays.
+pub struct Stats<R: Measurement> {
+    min: Option<R>,
+    max: Option<R>,
+    last: Option<R>,
+}
+
+impl<R: Measurement> Stats<R> {
+    pub fn new() -> Self {
+        Self {
+            min: None,
+            max: None,
+            last: None,
+        }
+    }
+
+    /// Accounts for a new reading.
+    pub fn update(&mut self, reading: &R) {
+        let min = self.min.get_or_insert_with(|| reading.clone());
+        let max = self.max.get_or_insert_with(|| reading.clone());
+        for index in 0..R::FIELDS {
+            let value = reading.field(index);
+            if value < min.field(index) {
+                min.set_field(index, value);
+            }
+            if value > max.field(index) {
+                max.set_field(index, value);
+            }
+        }
+        self.last = Some(reading.clone());
+    }
+
+    /// Field-wise minimum since creation or the last reset.
+    pub fn min(&self) -> Option<&R> {
+        self.min.as_ref()
+    }
+
+    /// Field-wise maximum since creation or the last reset.
+    pub fn max(&self) -> Option<&R> {
+        self.max.as_ref()
+    }
+
+    /// Most recent reading since creation or the last reset.
+    pub fn last(&self) -> Option<&R> {
+        self.last.as_ref()
+    }
+
+    /// Forgets all readings.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<R: Measurement> Default for Stats<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,73 @@
+/// Edge of a [`Threshold`] crossed by a reading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Crossed {
+    /// The reading rose to or above the on level.
+    Above,
+    /// The reading fell below the off level.
+    Below,
+    /// No edge was crossed.
+    None,
+}
+
+/// CO2 threshold with hysteresis, turning on at or above one level and off below a lower
+/// one, e.g. to run a fan above 1000 ppm until the concentration falls below 800 ppm.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Threshold {
+    on: u16,
+    off: u16,
+    above: bool,
+}
+
+impl Threshold {
+    /// Creates a threshold turning on at `on` ppm and off below `off` ppm, starting off.
+    ///
+    /// `off` should be below `on`, the difference is the hysteresis.
+    pub fn new(on: u16, off: u16) -> Self {
+        Self {
+            on,
+            off,
+            above: false,
+        }
+    }
+
+    /// Accounts for a CO2 reading in ppm, e.g. `response.co2 as u16`, and returns the
+    /// edge it crossed.
+    pub fn update(&mut self, co2: u16) -> Crossed {
+        if !self.above && co2 >= self.on {
+            self.above = true;
+            Crossed::Above
+        } else if self.above && co2 < self.off {
+            self.above = false;
+            Crossed::Below
+        } else {
+            Crossed::None
+        }
+    }
+
+    /// Whether the threshold is currently on.
+    pub fn is_above(&self) -> bool {
+        self.above
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_at_hysteresis_boundaries() {
+        let mut threshold = Threshold::new(1000, 800);
+        assert_eq!(threshold.update(999), Crossed::None);
+        assert!(!threshold.is_above());
+        assert_eq!(threshold.update(1000), Crossed::Above);
+        assert!(threshold.is_above());
+        assert_eq!(threshold.update(1000), Crossed::None);
+        assert_eq!(threshold.update(800), Crossed::None);
+        assert!(threshold.is_above());
+        assert_eq!(threshold.update(799), Crossed::Below);
+        assert!(!threshold.is_above());
+        assert_eq!(threshold.update(799), Crossed::None);
+    }
+}
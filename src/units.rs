@@ -0,0 +1,63 @@
+//! Newtypes for the physical units of readings, making the unit of a value part of its
+//! type.
+
+use crate::SCD41Response;
+
+/// Temperature in °C.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Celsius(pub f32);
+
+/// Relative humidity in %.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelativeHumidity(pub f32);
+
+/// Concentration in parts per million.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ppm(pub u16);
+
+/// Pressure in Pa.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pascals(pub f32);
+
+/// Pressure in hPa.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hectopascals(pub f32);
+
+impl From<Hectopascals> for Pascals {
+    fn from(pressure: Hectopascals) -> Self {
+        Pascals(pressure.0 * 100f32)
+    }
+}
+
+impl From<Pascals> for Hectopascals {
+    fn from(pressure: Pascals) -> Self {
+        Hectopascals(pressure.0 / 100f32)
+    }
+}
+
+impl SCD41Response {
+    /// CO2 concentration.
+    pub fn co2_ppm(&self) -> Ppm {
+        Ppm(self.co2 as u16)
+    }
+
+    /// Temperature.
+    pub fn temperature_celsius(&self) -> Celsius {
+        Celsius(self.temperature)
+    }
+
+    /// Relative humidity.
+    pub fn relative_humidity(&self) -> RelativeHumidity {
+        RelativeHumidity(self.humidity)
+    }
+}